@@ -1,13 +1,16 @@
-use chrono::{DateTime, Datelike, TimeZone, Local, Date};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use yansi::{Style};
 use itertools::Itertools;
 use structopt::StructOpt;
 
 use crate::cursorfile;
+use crate::defaults;
 use crate::input;
-use crate::config::{Config,CalendarConfig};
+use crate::config::{AgendaConfig,Config,CalendarConfig};
 use crate::khevent::KhEvent;
 use crate::khline::KhLine;
+use crate::output::{self, EventRecord, OutputArgs, OutputFormat};
+use crate::utils::template;
 use crate::KhResult;
 
 #[derive(Debug, StructOpt)]
@@ -15,11 +18,25 @@ pub struct AgendaArgs {
   /// Show agenda view
   #[structopt(name = "args")]
   pub args: Vec<String>,
+  #[structopt(flatten)]
+  pub output: OutputArgs,
+  /// show the times in this timezone instead of the local one, e.g. America/New_York
+  #[structopt(long = "tz")]
+  pub timezone: Option<String>,
 }
 
-pub fn show_events(config: &Config, args: &[&str]) -> KhResult<()> {
+pub fn show_events(config: &Config, args: &[&str], format: OutputFormat, timezone: Option<&str>) -> KhResult<()> {
+  if timezone.is_some() {
+    defaults::set_display_timezone(timezone)?;
+  }
+
   let mut events = input::selection(args)?;
 
+  if format != OutputFormat::Text {
+    let records: Vec<EventRecord> = events.map(|event| EventRecord::from(&event)).collect();
+    return output::print_records(format, &records);
+  }
+
   let cursor = cursorfile::read_cursorfile().ok();
   show_events_cursor(config, &mut events, cursor.as_ref());
 
@@ -44,10 +61,9 @@ pub fn show_events_cursor(
   let start_day = match cals_iter.peek() {
     Some((_, event, _)) => {
       event
-        .get_start()
-        .map(|dtstart| dtstart.into())
-        .unwrap_or_else(|| Local.timestamp(0, 0))
-        .date()
+        .get_display_start()
+        .map(|dtstart| dtstart.date())
+        .unwrap_or_else(|| NaiveDate::from_ymd(1970, 1, 1))
     }
     None => return,
   };
@@ -62,7 +78,7 @@ pub fn show_events_cursor(
     not_over_yet.retain( |(index, event, cal_config)| {
       let is_cursor = cursor.map(|c| c.matches_khevent(&event)).unwrap_or(false);
       maybe_print_date_line(&config, cur_day, start_day, &mut last_printed_day);
-      print_event_line(*cal_config, *index, &event, cur_day, is_cursor);
+      print_event_line(&config.agenda, *cal_config, *index, &event, cur_day, is_cursor);
       event.continues_after(cur_day)
     });
 
@@ -70,7 +86,7 @@ pub fn show_events_cursor(
     for (i, event, cal_config) in relevant_events {
       let is_cursor = cursor.map(|c| c.matches_khevent(&event)).unwrap_or(false);
       maybe_print_date_line(&config, cur_day, start_day, &mut last_printed_day);
-      print_event_line(cal_config, i, &event, cur_day, is_cursor);
+      print_event_line(&config.agenda, cal_config, i, &event, cur_day, is_cursor);
       if event.continues_after(cur_day) {
         not_over_yet.push((i, event, cal_config));
       }
@@ -78,7 +94,7 @@ pub fn show_events_cursor(
   }
 }
 
-fn maybe_print_week_separator(config: &Config, date: Date<Local>, start_date: Date<Local>, last_printed_date: Date<Local>) {
+fn maybe_print_week_separator(config: &Config, date: NaiveDate, start_date: NaiveDate, last_printed_date: NaiveDate) {
   if !config.agenda.print_week_separator {
     return;
   }
@@ -87,14 +103,14 @@ fn maybe_print_week_separator(config: &Config, date: Date<Local>, start_date: Da
   }
 }
 
-fn maybe_print_date_line_header(config: &Config, date: Date<Local>, start_date: Date<Local>, last_printed_date: &mut Date<Local>) {
+fn maybe_print_date_line_header(config: &Config, date: NaiveDate, start_date: NaiveDate, last_printed_date: &mut NaiveDate) {
   if !config.agenda.print_empty_days {
     return;
   }
   maybe_print_date_line(config, date, start_date, last_printed_date);
 }
 
-fn maybe_print_date_line(config: &Config, date: Date<Local>, start_date: Date<Local>, last_printed_date: &mut Date<Local>) {
+fn maybe_print_date_line(config: &Config, date: NaiveDate, start_date: NaiveDate, last_printed_date: &mut NaiveDate) {
   if date <= *last_printed_date {
     return;
   }
@@ -103,83 +119,129 @@ fn maybe_print_date_line(config: &Config, date: Date<Local>, start_date: Date<Lo
   *last_printed_date = date;
 }
 
-fn print_date_line(date: Date<Local>) {
-  let style_heading = Style::default().bold();
-  khprintln!("{}, {}", style_heading.paint(date.format("%Y-%m-%d")), date.format("%A"));
+fn print_date_line(date: NaiveDate) {
+  match defaults::get_date_format().format_header(date) {
+    Ok(header) => khprintln!("{}", header),
+    Err(error) => warn!("{}", error),
+  }
 }
 
 fn print_event_line(
+  agenda_config: &AgendaConfig,
   config: Option<&CalendarConfig>,
   index: usize,
   event: &KhEvent,
-  date: Date<Local>,
+  date: NaiveDate,
   is_cursor: bool
 ) {
-  match event_line(config, &event, date, is_cursor) {
-    Ok(line) => khprintln!("{:4}  {}", index, line),
+  match event_line(agenda_config, config, index, &event, date, is_cursor) {
+    Ok(line) => khprintln!("{}", line),
     Err(error) => warn!("{} in {}", error, event.get_uid())
   }
 }
 
 pub fn event_line(
+  agenda_config: &AgendaConfig,
   config: Option<&CalendarConfig>,
+  index: usize,
   event: &KhEvent,
-  cur_day: Date<Local>,
+  cur_day: NaiveDate,
   is_cursor: bool
 ) -> Result<String, String> {
   if !event.relevant_on(cur_day) {
     return Err(format!("event is not relevant for {:?}", cur_day));
   }
 
-  let mut summary = event.get_summary().ok_or("Invalid SUMMARY")?;
-  if let Some(config) = config {
-    let calendar_style = config.get_style_for_calendar();
-    summary = calendar_style.paint(summary).to_string();
-  }
+  let summary = event.get_summary().ok_or("Invalid SUMMARY")?;
+  let calendar_style = config.map(|config| config.get_style_for_calendar()).unwrap_or_default();
 
   let cursor_icon = if is_cursor { ">" } else { "" };
 
-  if event.is_allday() {
-    Ok(format!("{:3}             {}", cursor_icon, summary))
-  } else {
+  let date_format = defaults::get_date_format();
+  let time_width = date_format.time_width();
+  let mut start_string = "".to_string();
+  let mut end_string = "".to_string();
+  // all-day events leave the time column blank, so it stays aligned
+  let mut time_string = " ".repeat(2 * time_width + 1);
+  if !event.is_allday() {
     let mut time_sep = " ";
-    let dtstart: DateTime<Local> = event.get_start().ok_or("Invalid DTSTART")?.into();
-    let start_string = if dtstart.date() != cur_day {
-      "".to_string()
-    } else {
+    let dtstart = event.get_display_start().ok_or("Invalid DTSTART")?;
+    if dtstart.date() == cur_day {
       time_sep = "-";
-      format!("{}", dtstart.format("%H:%M"))
-    };
+      start_string = date_format.format_time(&dtstart);
+    }
 
-    let dtend: DateTime<Local> = event.get_end().ok_or("Invalid DTEND")?.into();
-    let end_string = if dtend.date() != cur_day {
-      "".to_string()
-    } else {
+    let dtend = event.get_display_end().ok_or("Invalid DTEND")?;
+    if dtend.date() == cur_day {
       time_sep = "-";
-      format!("{}", dtend.format("%H:%M"))
-    };
+      end_string = date_format.format_time(&dtend);
+    }
 
-    Ok(format!("{:3}{:5}{}{:5}  {}", cursor_icon, start_string, time_sep, end_string, summary))
+    time_string = format!("{:width$}{}{:width$}", start_string, time_sep, end_string, width = time_width);
   }
+
+  let line_format = agenda_config.get_line_format(is_cursor, event.is_allday(), !event.starts_on(cur_day));
+  template::render(line_format, |field| {
+    let value = match field {
+      "index" => index.to_string(),
+      "cursor" => cursor_icon.to_string(),
+      "start" => start_string.clone(),
+      "end" => end_string.clone(),
+      "time" => time_string.clone(),
+      "summary" => return Some((summary.clone(), calendar_style)),
+      "calendar" => return Some((event.get_calendar_name().unwrap_or_default(), calendar_style)),
+      "location" => event.get_location().unwrap_or_default(),
+      "uid" => event.get_uid(),
+      "tzid" => event.get_original_tzid().unwrap_or_default(),
+      _ => return None,
+    };
+    Some((value, Style::default()))
+  })
 }
 
 impl KhEvent {
-  fn starts_on(&self, date: Date<Local>) -> bool {
-    let dtstart: Date<Local> = self.get_start().unwrap().into();
+  fn get_display_start(&self) -> Option<NaiveDateTime> {
+    let timezone = defaults::get_display_timezone();
+    self.get_start().map(|dtstart| dtstart.to_naive_datetime(&timezone))
+  }
+
+  fn get_display_end(&self) -> Option<NaiveDateTime> {
+    let timezone = defaults::get_display_timezone();
+    self.get_end().map(|dtend| dtend.to_naive_datetime(&timezone))
+  }
+
+  fn get_display_last_relevant_date(&self) -> Option<NaiveDate> {
+    let timezone = defaults::get_display_timezone();
+    self.get_last_relevant_date().map(|date| date.to_naive_datetime(&timezone).date())
+  }
+
+  /// The name of the event's timezone, if it differs from the timezone it is
+  /// shown in.
+  fn get_original_tzid(&self) -> Option<String> {
+    let name = self.get_start_timezone()?.get_name();
+    if name == defaults::get_display_timezone().get_name() {
+      None
+    } else {
+      Some(name)
+    }
+  }
+
+  fn starts_on(&self, date: NaiveDate) -> bool {
+    let dtstart = self.get_display_start().unwrap().date();
     dtstart == date
   }
 
-  fn relevant_on(&self, date: Date<Local>) -> bool {
-    let dtstart: Option<Date<Local>> = self.get_start().map(|date| date.into());
-    let last_relevant_date: Option<Date<Local>> = self.get_last_relevant_date().map(|date| date.into());
+  pub fn relevant_on(&self, date: NaiveDate) -> bool {
+    let dtstart = self.get_display_start().map(|dtstart| dtstart.date());
+    let last_relevant_date = self.get_display_last_relevant_date();
 
     dtstart.map(|dtstart| dtstart <= date).unwrap_or(false) &&
     last_relevant_date.map(|enddate| enddate >= date).unwrap_or(false)
   }
 
-  fn continues_after(&self, date: Date<Local>) -> bool {
-    let last_relevant_date: Option<Date<Local>> = self.get_last_relevant_date().map(|date| date.into());
-    last_relevant_date
+  fn continues_after(&self, date: NaiveDate) -> bool {
+    self
+      .get_display_last_relevant_date()
       .map(|enddate| enddate > date)
       .unwrap_or(false)
   }
@@ -193,18 +255,19 @@ mod integration {
   use crate::utils::stdioutils;
   use crate::config::Config;
   use crate::icalwrap::IcalVCalendar;
+  use crate::utils::dateformat::{DateFormat, Locale};
 
-  use chrono::{Local, TimeZone};
+  use chrono::NaiveDate;
 
   #[test]
   fn test_starts_on() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
     let event = cal.get_principal_khevent();
 
-    let first_day = Local.ymd(2007, 6, 28);
+    let first_day = NaiveDate::from_ymd(2007, 6, 28);
     assert!(event.starts_on(first_day));
 
-    let last_day = Local.ymd(2007, 7, 7);
+    let last_day = NaiveDate::from_ymd(2007, 7, 7);
     assert!(!event.starts_on(last_day));
   }
 
@@ -212,9 +275,9 @@ mod integration {
   fn test_continues_after_allday() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY_ALLDAY, None).unwrap();
     let event = cal.get_principal_khevent();
-    let first_day = Local.ymd(2007, 6, 28);
+    let first_day = NaiveDate::from_ymd(2007, 6, 28);
     assert!(event.continues_after(first_day));
-    let last_day = Local.ymd(2007, 7, 8);
+    let last_day = NaiveDate::from_ymd(2007, 7, 8);
     assert!(!event.continues_after(last_day));
   }
 
@@ -222,7 +285,7 @@ mod integration {
   fn test_continues_after_simple() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
     let event = cal.get_principal_khevent();
-    let date = Local.ymd(1997, 3, 24);
+    let date = NaiveDate::from_ymd(1997, 3, 24);
     assert!(!event.continues_after(date));
   }
 
@@ -230,8 +293,8 @@ mod integration {
   fn test_event_line_negative() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
     let event = cal.get_principal_khevent();
-    let date = Local.ymd(1998, 1, 1);
-    let event_line = event_line(None, &event, date, false);
+    let date = NaiveDate::from_ymd(1998, 1, 1);
+    let event_line = event_line(&AgendaConfig::default(), None, 0, &event, date, false);
     assert!(event_line.is_err())
   }
 
@@ -240,9 +303,9 @@ mod integration {
     testdata::setup();
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
     let event = cal.get_principal_khevent();
-    let date = Local.ymd(1997, 3, 24);
-    let event_line = event_line(None, &event, date, false).unwrap();
-    assert_eq!("   13:30-22:00  Calendaring Interoperability Planning Meeting".to_string(), event_line)
+    let date = NaiveDate::from_ymd(1997, 3, 24);
+    let event_line = event_line(&AgendaConfig::default(), None, 0, &event, date, false).unwrap();
+    assert_eq!("   0     13:30-22:00  Calendaring Interoperability Planning Meeting".to_string(), event_line)
   }
 
   #[test]
@@ -250,9 +313,9 @@ mod integration {
     testdata::setup();
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
     let event = cal.get_principal_khevent();
-    let date = Local.ymd(1997, 3, 24);
-    let event_line = event_line(None, &event, date, true).unwrap();
-    assert_eq!(">  13:30-22:00  Calendaring Interoperability Planning Meeting".to_string(), event_line)
+    let date = NaiveDate::from_ymd(1997, 3, 24);
+    let event_line = event_line(&AgendaConfig::default(), None, 0, &event, date, true).unwrap();
+    assert_eq!("   0  >  13:30-22:00  Calendaring Interoperability Planning Meeting".to_string(), event_line)
   }
 
   #[test]
@@ -260,24 +323,24 @@ mod integration {
     testdata::setup();
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
     let event = cal.get_principal_khevent();
-    let begin = Local.ymd(2007, 6, 28);
-    let middle = Local.ymd(2007, 6, 30);
-    let end = Local.ymd(2007, 7, 9);
-    let event_line_begin = event_line(None, &event, begin, false).unwrap();
-    let event_line_middle = event_line(None, &event, middle, false).unwrap();
-    let event_line_end = event_line(None, &event, end, false).unwrap();
-    assert_eq!("   15:29-       Festival International de Jazz de Montreal".to_string(), event_line_begin);
-    assert_eq!("                Festival International de Jazz de Montreal".to_string(), event_line_middle);
-    assert_eq!("        -09:29  Festival International de Jazz de Montreal".to_string(), event_line_end);
+    let begin = NaiveDate::from_ymd(2007, 6, 28);
+    let middle = NaiveDate::from_ymd(2007, 6, 30);
+    let end = NaiveDate::from_ymd(2007, 7, 9);
+    let event_line_begin = event_line(&AgendaConfig::default(), None, 0, &event, begin, false).unwrap();
+    let event_line_middle = event_line(&AgendaConfig::default(), None, 0, &event, middle, false).unwrap();
+    let event_line_end = event_line(&AgendaConfig::default(), None, 0, &event, end, false).unwrap();
+    assert_eq!("   0     15:29-       Festival International de Jazz de Montreal".to_string(), event_line_begin);
+    assert_eq!("   0                  Festival International de Jazz de Montreal".to_string(), event_line_middle);
+    assert_eq!("   0          -09:29  Festival International de Jazz de Montreal".to_string(), event_line_end);
   }
 
   #[test]
   fn test_event_line_multiday_allday() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY_ALLDAY, None).unwrap();
     let event = cal.get_principal_khevent();
-    let date = Local.ymd(2007, 6, 28);
-    let event_line = event_line(None, &event, date, false).unwrap();
-    assert_eq!("                Festival International de Jazz de Montreal".to_string(), event_line)
+    let date = NaiveDate::from_ymd(2007, 6, 28);
+    let event_line = event_line(&AgendaConfig::default(), None, 0, &event, date, false).unwrap();
+    assert_eq!("   0                  Festival International de Jazz de Montreal".to_string(), event_line)
   }
 
   #[test]
@@ -285,7 +348,7 @@ mod integration {
     testdata::setup();
     let _testdir = prepare_testdir("testdir_with_seq");
 
-    show_events(&Config::read_config(), &[]).unwrap();
+    show_events(&Config::read_config(), &[], OutputFormat::Text, None).unwrap();
 
     let stdout = stdioutils::test_stdout_clear();
     let expected = indoc!("
@@ -302,4 +365,90 @@ mod integration {
    ");
     assert_eq!(expected, stdout);
   }
+
+  #[test]
+  fn test_event_line_template() {
+    testdata::setup();
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+    let event = cal.get_principal_khevent();
+    let agenda_config = AgendaConfig {
+      event_format: "{index:>3} {start}-{end} {summary:.8} @ {location}".to_string(),
+      continuation_format: Some("{index:>3} ... {summary:.8}".to_string()),
+      ..AgendaConfig::default()
+    };
+
+    let event_line_begin = event_line(&agenda_config, None, 7, &event, NaiveDate::from_ymd(2007, 6, 28), false).unwrap();
+    let event_line_middle = event_line(&agenda_config, None, 7, &event, NaiveDate::from_ymd(2007, 6, 30), false).unwrap();
+
+    assert_eq!("  7 15:29- Festival @ LDB Lobby", event_line_begin);
+    assert_eq!("  7 ... Festival", event_line_middle);
+  }
+
+  #[test]
+  fn test_stdout_localized() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_seq");
+    defaults::set_date_format(DateFormat {
+      locale: Locale::De,
+      time_format: "%I:%M%p".to_string(),
+      header_format: "{weekday}, {date}".to_string(),
+      ..DateFormat::default()
+    });
+
+    show_events(&Config::read_config(), &[], OutputFormat::Text, None).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!("Donnerstag, 2018-12-13", lines[0]);
+    assert_eq!(format!("   0     11:30PM-{}shows up on two days", " ".repeat(9)), lines[1]);
+    assert_eq!("Freitag, 2018-12-14", lines[2]);
+  }
+
+  #[test]
+  fn test_stdout_timezone() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_seq");
+    let agenda_config = AgendaConfig {
+      event_format: "{index:>4}  {time}  {summary} {tzid}".to_string(),
+      ..AgendaConfig::default()
+    };
+    let config = Config { agenda: agenda_config, ..Config::default() };
+
+    show_events(&config, &[], OutputFormat::Text, Some("America/New_York")).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let expected = indoc!("
+      2018-12-13, Thursday
+         0  17:30-       shows up on two days Europe/Berlin
+      2018-12-14, Friday
+         0               shows up on two days Europe/Berlin
+      2018-12-15, Saturday
+         0               shows up on two days Europe/Berlin
+      2018-12-16, Sunday
+         0               shows up on two days Europe/Berlin
+      2018-12-17, Monday
+         0       -13:30  shows up on two days Europe/Berlin
+   ");
+    assert_eq!(expected, stdout);
+  }
+
+  #[test]
+  fn test_stdout_timezone_unknown() {
+    let _testdir = prepare_testdir("testdir_with_seq");
+
+    assert!(show_events(&Config::read_config(), &[], OutputFormat::Text, Some("Nowhere/Special")).is_err());
+  }
+
+  #[test]
+  fn test_stdout_jsonl() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_seq");
+
+    show_events(&Config::read_config(), &[], OutputFormat::Jsonl, None).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    assert_eq!(1, stdout.lines().count());
+    assert!(stdout.contains(r#""summary":"shows up on two days""#));
+    assert!(stdout.contains(r#""allday":false"#));
+  }
 }
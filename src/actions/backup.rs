@@ -0,0 +1,131 @@
+use crate::defaults;
+use crate::journal;
+use crate::KhResult;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct BackupArgs {
+  #[structopt(subcommand)]
+  pub backup_cmd: BackupCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum BackupCommand {
+  /// Remove old backups
+  ///
+  /// operations whose backup is removed can no longer be undone
+  #[structopt(name = "prune", author = "")]
+  Prune {
+    /// keep the backups of this many days (default: keep_days from the [backup] config section)
+    #[structopt(long = "keep-days")]
+    keep_days: Option<u32>,
+    /// keep the backups of this many operations (default: keep_operations from the [backup] config section)
+    #[structopt(long = "keep-operations")]
+    keep_operations: Option<usize>,
+  },
+}
+
+pub fn do_backup(args: &BackupArgs) -> KhResult<()> {
+  match &args.backup_cmd {
+    BackupCommand::Prune { keep_days, keep_operations } => prune(*keep_days, *keep_operations),
+  }
+}
+
+fn prune(keep_days: Option<u32>, keep_operations: Option<usize>) -> KhResult<()> {
+  let (keep_days, keep_operations) = if keep_days.is_some() || keep_operations.is_some() {
+    (keep_days, keep_operations)
+  } else {
+    defaults::get_backup_retention()
+  };
+
+  if keep_days.is_none() && keep_operations.is_none() {
+    Err("no retention policy given or configured, not pruning anything")?
+  }
+
+  for id in journal::prune(keep_days, keep_operations)? {
+    khprintln!("{}", id);
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Backup;
+  use crate::journal::Operation;
+  use crate::testdata;
+  use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+  use structopt::StructOpt;
+
+  fn commit_operation(testdir: &assert_fs::TempDir) {
+    let mut operation = Operation::begin("copy").unwrap();
+    operation.created(testdir.child(".khaleesi/cal/new.ics").path());
+    operation.commit().unwrap();
+  }
+
+  #[test]
+  fn test_prune_keep_operations() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir_with_backup");
+    commit_operation(&testdir);
+    commit_operation(&testdir);
+    commit_operation(&testdir);
+
+    let args = CommandLine::from_iter(&["khaleesi", "backup", "prune", "--keep-operations", "1"]);
+    if let Backup(x) = args.cmd {
+      do_backup(&x).unwrap();
+    }
+
+    assert_eq!("2013-01-01T02:02:03\n2013-01-01T02:02:03-1\n", stdioutils::test_stdout_clear());
+    testdir.child(".khaleesi/backup/2013-01-01T02:02:03-2").assert(predicate::path::exists());
+    testdir.child(".khaleesi/backup/backup_id").assert(predicate::path::exists());
+    assert_eq!(1, journal::read_journal().unwrap().len());
+  }
+
+  #[test]
+  fn test_prune_keep_days() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir");
+    testdir.child(".khaleesi/backup/2012-12-01T10:00:00/cal.ics").touch().unwrap();
+    testdir.child(".khaleesi/backup/2012-12-30T10:00:00/cal.ics").touch().unwrap();
+
+    let args = CommandLine::from_iter(&["khaleesi", "backup", "prune", "--keep-days", "14"]);
+    if let Backup(x) = args.cmd {
+      do_backup(&x).unwrap();
+    }
+
+    assert_eq!("2012-12-01T10:00:00\n", stdioutils::test_stdout_clear());
+    testdir.child(".khaleesi/backup/2012-12-01T10:00:00").assert(predicate::path::missing());
+    testdir.child(".khaleesi/backup/2012-12-30T10:00:00").assert(predicate::path::exists());
+  }
+
+  #[test]
+  fn test_prune_from_config_on_commit() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir");
+    defaults::set_backup_retention(None, Some(2));
+
+    commit_operation(&testdir);
+    commit_operation(&testdir);
+    commit_operation(&testdir);
+
+    testdir.child(".khaleesi/backup/2013-01-01T02:02:03").assert(predicate::path::missing());
+    testdir.child(".khaleesi/backup/2013-01-01T02:02:03-2").assert(predicate::path::exists());
+    assert_eq!(2, journal::read_journal().unwrap().len());
+  }
+
+  #[test]
+  fn test_prune_no_policy() {
+    let _testdir = prepare_testdir("testdir");
+    let args = CommandLine::from_iter(&["khaleesi", "backup", "prune"]);
+    if let Backup(x) = args.cmd {
+      assert!(do_backup(&x).is_err());
+    }
+  }
+}
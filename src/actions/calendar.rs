@@ -0,0 +1,302 @@
+use std::cmp;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use structopt::StructOpt;
+use yansi::Style;
+
+use crate::actions::select;
+use crate::config::Config;
+use crate::defaults;
+use crate::khevent::KhEvent;
+use crate::khline::KhLine;
+use crate::utils::dateutil;
+use crate::KhResult;
+
+const CELL_WIDTH: usize = 10;
+const MONTH_CELL_LINES: usize = 3;
+const WEEK_FIRST_HOUR: u32 = 8;
+const WEEK_LAST_HOUR: u32 = 18;
+
+#[derive(Debug, StructOpt)]
+pub struct CalendarArgs {
+  #[structopt(subcommand)]
+  pub calendar_cmd: CalendarCommand,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum CalendarCommand {
+  /// Show the events of a month as a grid
+  #[structopt(name = "month", author = "")]
+  Month {
+    /// the month to show, e.g. 2019-03 or next month (default: this month)
+    #[structopt(name = "month")]
+    month: Vec<String>,
+  },
+  /// Show the events of a week in seven day columns
+  #[structopt(name = "week", author = "")]
+  Week {
+    /// a day of the week to show, e.g. 2019-03-13 or next week (default: this week)
+    #[structopt(name = "week")]
+    week: Vec<String>,
+  },
+}
+
+pub fn do_calendar(args: &CalendarArgs, config: &Config) -> KhResult<()> {
+  match &args.calendar_cmd {
+    CalendarCommand::Month { month } => show_month(config, first_day_of_range(month, "this month")?),
+    CalendarCommand::Week { week } => show_week(config, first_day_of_range(week, "this week")?),
+  }
+}
+
+/// The views work on plain dates, so that days with a daylight saving change
+/// are no different from others.
+fn first_day_of_range(args: &[String], default: &str) -> KhResult<NaiveDate> {
+  let range_str = if args.is_empty() { default.to_string() } else { args.join(" ") };
+  let (begin, _) = dateutil::daterange_from_str(&range_str)?;
+  Ok(begin.naive_local())
+}
+
+fn monday_of(date: NaiveDate) -> NaiveDate {
+  date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+fn show_month(config: &Config, date: NaiveDate) -> KhResult<()> {
+  let (first_day, last_day) = dateutil::month_range(date.year(), date.month());
+  let (first_day, last_day) = (first_day.naive_local(), last_day.naive_local());
+  let events = events_between(first_day, last_day)?;
+
+  let date_format = defaults::get_date_format();
+  let style_heading = Style::default().bold();
+  khprintln!("{}", style_heading.paint(date_format.format(first_day, "%B %Y")));
+  let mut week_begin = monday_of(first_day);
+  let weekdays: Vec<String> = (0..7).map(|i| date_format.format(week_begin + Duration::days(i), "%a")).collect();
+  print_row(weekdays.iter().map(|weekday| cell(weekday, Style::default())));
+
+  while week_begin <= last_day {
+    let days: Vec<NaiveDate> = (0..7)
+      .map(|i| week_begin + Duration::days(i))
+      .collect();
+    let day_events: Vec<Vec<&KhEvent>> = days
+      .iter()
+      .map(|day| {
+        if day.month() != first_day.month() {
+          return Vec::new();
+        }
+        events.iter().filter(|event| event.relevant_on(*day)).collect()
+      })
+      .collect();
+
+    print_row(days.iter().map(|day| {
+      let day_number = if day.month() == first_day.month() { day.day().to_string() } else { "".to_string() };
+      cell(&day_number, Style::default())
+    }));
+
+    let line_count = day_events.iter().map(Vec::len).max().unwrap_or(0).min(MONTH_CELL_LINES);
+    for line in 0..line_count {
+      print_row(day_events.iter().map(|events| {
+        if line == MONTH_CELL_LINES - 1 && events.len() > MONTH_CELL_LINES {
+          return cell(&format!("+{} more", events.len() - line), Style::default());
+        }
+        match events.get(line) {
+          Some(event) => cell(&event.get_summary().unwrap_or_default(), calendar_style(config, event)),
+          None => cell("", Style::default()),
+        }
+      }));
+    }
+
+    week_begin = week_begin + Duration::weeks(1);
+  }
+
+  Ok(())
+}
+
+fn show_week(config: &Config, date: NaiveDate) -> KhResult<()> {
+  let first_day = monday_of(date);
+  let last_day = first_day + Duration::days(6);
+  let events = events_between(first_day, last_day)?;
+  let days: Vec<NaiveDate> = (0..7).map(|i| first_day + Duration::days(i)).collect();
+
+  let date_format = defaults::get_date_format();
+  print_time_row("", days.iter().map(|day| cell(&date_format.format(*day, "%a %m-%d"), Style::default())));
+
+  let allday_events: Vec<Vec<&KhEvent>> = days
+    .iter()
+    .map(|day| events.iter().filter(|event| event.is_allday() && event.relevant_on(*day)).collect())
+    .collect();
+  let allday_lines = allday_events.iter().map(Vec::len).max().unwrap_or(0);
+  for line in 0..allday_lines {
+    print_time_row("", allday_events.iter().map(|events| match events.get(line) {
+      Some(event) => cell(&event.get_summary().unwrap_or_default(), calendar_style(config, event)),
+      None => cell("", Style::default()),
+    }));
+  }
+
+  let blocks: Vec<Vec<TimeBlock>> = days
+    .iter()
+    .map(|day| {
+      events
+        .iter()
+        .filter(|event| !event.is_allday() && event.relevant_on(*day))
+        .filter_map(|event| TimeBlock::on_day(event, *day))
+        .collect()
+    })
+    .collect();
+
+  let first_hour = blocks.iter().flatten().map(|block| block.start.hour()).min().unwrap_or(WEEK_FIRST_HOUR);
+  let last_hour = blocks.iter().flatten().map(TimeBlock::last_hour).max().unwrap_or(WEEK_LAST_HOUR);
+  for hour in cmp::min(first_hour, WEEK_FIRST_HOUR)..=cmp::max(last_hour, WEEK_LAST_HOUR) {
+    print_time_row(&format!("{:02}:00", hour), days.iter().zip(blocks.iter()).map(|(day, blocks)| {
+      let slot_start = day.and_hms(hour, 0, 0);
+      let slot_end = slot_start + Duration::hours(1);
+      let in_slot: Vec<&TimeBlock> = blocks
+        .iter()
+        .filter(|block| block.start < slot_end && block.end > slot_start)
+        .collect();
+      let starting = in_slot.iter().find(|block| block.start >= slot_start);
+      match (in_slot.first(), starting) {
+        (_, Some(_)) if in_slot.len() > 1 => cell(&format!("{} events", in_slot.len()), Style::default()),
+        (_, Some(block)) => cell(&block.event.get_summary().unwrap_or_default(), calendar_style(config, block.event)),
+        (Some(block), None) => cell("|", calendar_style(config, block.event)),
+        (None, None) => cell("", Style::default()),
+      }
+    }));
+  }
+
+  Ok(())
+}
+
+/// The part of a timed event that falls on one day.
+struct TimeBlock<'a> {
+  event: &'a KhEvent,
+  start: NaiveDateTime,
+  end: NaiveDateTime,
+}
+
+impl<'a> TimeBlock<'a> {
+  fn on_day(event: &'a KhEvent, day: NaiveDate) -> Option<Self> {
+    let timezone = defaults::get_display_timezone();
+    let start = event.get_start()?.to_naive_datetime(&timezone);
+    let end = event.get_end().map(|end| end.to_naive_datetime(&timezone)).unwrap_or(start);
+    // events without a duration still take up a line
+    let end = cmp::max(end, start + Duration::minutes(1));
+
+    let day_start = day.and_hms(0, 0, 0);
+    let day_end = day.succ().and_hms(0, 0, 0);
+    Some(TimeBlock {
+      event,
+      start: cmp::max(start, day_start),
+      end: cmp::min(end, day_end),
+    })
+  }
+
+  fn last_hour(&self) -> u32 {
+    (self.end - Duration::seconds(1)).hour()
+  }
+}
+
+fn events_between(first_day: NaiveDate, last_day: NaiveDate) -> KhResult<Vec<KhEvent>> {
+  let from = first_day.format("%Y-%m-%d").to_string();
+  let to = last_day.format("%Y-%m-%d").to_string();
+  let lines = select::select_lines(&["from", &from, "to", &to])?;
+
+  let events = lines
+    .iter()
+    .filter_map(|line| line.parse::<KhLine>().ok())
+    .filter_map(|khline| khline.to_event().ok())
+    .collect();
+  Ok(events)
+}
+
+fn calendar_style(config: &Config, event: &KhEvent) -> Style {
+  event
+    .get_calendar_name()
+    .and_then(|name| config.get_config_for_calendar(&name))
+    .map(|cal_config| cal_config.get_style_for_calendar())
+    .unwrap_or_default()
+}
+
+/// Truncates or pads the text to the cell width, then paints it.
+fn cell(text: &str, style: Style) -> String {
+  let text: String = text.chars().take(CELL_WIDTH).collect();
+  let text = format!("{:width$}", text, width = CELL_WIDTH);
+  if style == Style::default() {
+    text
+  } else {
+    style.paint(text).to_string()
+  }
+}
+
+fn print_row(cells: impl Iterator<Item = String>) {
+  let row = cells.collect::<Vec<String>>().join(" ");
+  khprintln!("{}", row.trim_end());
+}
+
+fn print_time_row(label: &str, cells: impl Iterator<Item = String>) {
+  let row = cells.collect::<Vec<String>>().join(" ");
+  khprintln!("{}", format!("{:5} {}", label, row).trim_end());
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::actions::index;
+  use crate::testdata;
+  use crate::testutils::{prepare_testdir, prepare_testdir_empty};
+  use crate::utils::stdioutils;
+
+  use assert_fs::prelude::*;
+
+  #[test]
+  fn test_show_month() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir");
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    show_month(&Config::default(), NaiveDate::from_ymd(2018, 12, 13)).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!("December 2018", lines[0]);
+    assert_eq!("Mon        Tue        Wed        Thu        Fri        Sat        Sun", lines[1]);
+    assert_eq!(format!("{}1          2", " ".repeat(55)), lines[2]);
+    assert_eq!(format!("{}shows up o shows up o shows up o shows up o", " ".repeat(33)), lines[5]);
+    assert_eq!("17         18         19         20         21         22         23", lines[6]);
+    assert_eq!("shows up o", lines[7]);
+  }
+
+  #[test]
+  fn test_show_week() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir");
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    show_week(&Config::default(), NaiveDate::from_ymd(2018, 12, 19)).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!("      Mon 12-17  Tue 12-18  Wed 12-19  Thu 12-20  Fri 12-21  Sat 12-22  Sun 12-23", lines[0]);
+    assert_eq!("00:00 shows up o", lines[1]);
+    assert_eq!("01:00 |", lines[2]);
+    assert_eq!("19:00 |", lines[20]);
+    assert_eq!(21, lines.len());
+  }
+
+  #[test]
+  fn test_show_week_dst() {
+    testdata::setup();
+    let testdir = prepare_testdir_empty();
+    testdir.child(".khaleesi/cal/dst.ics").write_str(testdata::TEST_EVENT_DST).unwrap();
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    show_week(&Config::default(), NaiveDate::from_ymd(2019, 3, 27)).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!("      Mon 03-25  Tue 03-26  Wed 03-27  Thu 03-28  Fri 03-29  Sat 03-30  Sun 03-31", lines[0]);
+    assert_eq!(format!("01:00 {}Night owl", " ".repeat(66)), lines[1]);
+    assert_eq!(format!("02:00 {}|", " ".repeat(66)), lines[2]);
+    assert_eq!(format!("03:00 {}|", " ".repeat(66)), lines[3]);
+    assert_eq!("04:00", lines[4]);
+  }
+}
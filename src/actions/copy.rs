@@ -1,19 +1,39 @@
+use crate::actions::index;
 use crate::input;
+use crate::journal::Operation;
+use crate::khline::KhLine;
 use crate::utils::fileutil;
 use crate::utils::misc;
 
 use crate::KhResult;
 
+use std::path::PathBuf;
+
 pub fn do_copy() -> KhResult<()> {
-  let khline = input::default_input_khline()?;
+  let khlines = input::default_input_khlines_or_cursor()?;
+
+  let mut operation = Operation::begin("copy")?;
+  let mut new_paths = Vec::new();
+  let result = khlines
+    .iter()
+    .try_for_each(|khline| copy(&mut operation, khline, &mut new_paths));
+  operation.commit()?;
+
+  index::update_index_or_warn(&new_paths);
 
+  result
+}
+
+fn copy(operation: &mut Operation, khline: &KhLine, new_paths: &mut Vec<PathBuf>) -> KhResult<()> {
   let uid = &misc::make_new_uid();
   let cal = khline.to_cal()?;
   let new_cal = cal.with_uid(uid)?.with_dtstamp_now();
+  let new_path = new_cal.get_path().ok_or("calendar has no path")?.clone();
 
+  operation.created(&new_path);
+  new_paths.push(new_path.clone());
   fileutil::write_cal(&new_cal)?;
-
-  info!("Successfully wrote file: {}", new_cal.get_path().unwrap().display());
+  info!("Successfully wrote file: {}", new_path.display());
 
   Ok(())
 }
@@ -24,7 +44,6 @@ mod integration {
   use super::*;
 
   use assert_fs::prelude::*;
-  use crate::khline::KhLine;
   use crate::testutils::prepare_testdir;
   use crate::utils::stdioutils;
   use predicates::prelude::*;
@@ -42,4 +61,17 @@ mod integration {
     let khline = "11111111-2222-3333-4444-444444444444@khaleesi.ics".parse::<KhLine>().unwrap();
     assert_eq!("11111111-2222-3333-4444-444444444444@khaleesi", khline.to_event().unwrap().get_uid());
   }
+
+  #[test]
+  fn copy_test_batch() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics\nsecond/second_sub/twodaysacrossbuckets.ics");
+
+    do_copy().unwrap();
+
+    let copies = fileutil::file_iter(testdir.child(".khaleesi/cal").path())
+      .filter(|path| path.ends_with("11111111-2222-3333-4444-444444444444@khaleesi.ics"))
+      .count();
+    assert_eq!(2, copies);
+  }
 }
@@ -1,40 +1,145 @@
+use crate::actions::index;
+use crate::icalwrap::IcalVCalendar;
 use crate::input;
-use crate::backup::backup;
+use crate::journal::Operation;
 use crate::KhResult;
 use crate::khline::KhLine;
-use crate::utils::stdioutils;
+use crate::utils::{fileutil, stdioutils};
 
-use std::path::PathBuf;
 use std::fs::remove_file;
+use structopt::StructOpt;
 
-pub fn do_delete() -> KhResult<()> {
+#[derive(Debug, StructOpt)]
+pub struct DeleteArgs {
+  /// only delete this occurrence of a recurring event
+  #[structopt(long = "this", conflicts_with = "following")]
+  pub this: bool,
+  /// delete this and all following occurrences of a recurring event
+  #[structopt(long = "following")]
+  pub following: bool,
+  /// do not ask for confirmation
+  #[structopt(short = "y", long = "yes")]
+  pub yes: bool,
+}
+
+/// A file that is affected by a delete. If nothing remains of the calendar,
+/// the file is removed.
+struct Deletion {
+  khline: KhLine,
+  remaining: Option<IcalVCalendar>,
+}
+
+pub fn do_delete(args: &DeleteArgs) -> KhResult<()> {
   info!("do_delete");
 
-  let cursor_khline = input::default_input_khline()?;
+  let khlines = input::default_input_khlines_or_cursor()?;
+  let deletions = plan_deletions(khlines, args)?;
 
-  delete_file(cursor_khline)
+  if !args.yes && !ask_really_delete(&deletions) {
+    return Ok(());
+  }
+
+  let mut operation = Operation::begin("delete")?;
+  let result = deletions
+    .iter()
+    .try_for_each(|deletion| apply_deletion(&mut operation, deletion));
+  operation.commit()?;
+
+  let paths = deletions
+    .iter()
+    .map(|deletion| deletion.khline.path.clone())
+    .collect::<Vec<_>>();
+  index::update_index_or_warn(&paths);
+
+  result
 }
 
-fn delete_file(khline: KhLine) -> KhResult<()> {
+/// Works out what to do with each file before anything is written, so that
+/// invalid input does not leave a batch half deleted. Several occurrences in
+/// the same file are removed from the same calendar.
+fn plan_deletions(khlines: Vec<KhLine>, args: &DeleteArgs) -> KhResult<Vec<Deletion>> {
+  let mut deletions: Vec<Deletion> = Vec::new();
+
+  for khline in khlines {
+    let index = deletions.iter().position(|deletion| deletion.khline.path == khline.path);
 
-  if ask_really_delete(&khline.path) {
-    let backup_path = backup(&khline).unwrap();
-    info!("Backup written to {}", backup_path.display());
+    if !args.this && !args.following {
+      match index {
+        Some(index) => deletions[index].remaining = None,
+        None => deletions.push(Deletion { khline, remaining: None }),
+      }
+      continue;
+    }
 
-    remove_file(khline.path.clone())?;
-    info!("deleted {:#?}", khline.get_normalized_path());
+    let cal = match index {
+      Some(index) => match &deletions[index].remaining {
+        Some(cal) => cal.clone(),
+        None => continue,
+      },
+      None => khline.to_cal()?,
+    };
+    let remaining = without_occurrence(&khline, cal, args.following)?;
+    match index {
+      Some(index) => deletions[index].remaining = remaining,
+      None => deletions.push(Deletion { khline, remaining }),
+    }
   }
 
+  Ok(deletions)
+}
+
+/// Removes the occurrence in `khline` from `cal` by adding an EXDATE, or with
+/// `following`, ends the recurrence right before it. Returns `None` if
+/// nothing would be left of the series.
+fn without_occurrence(khline: &KhLine, cal: IcalVCalendar, following: bool) -> KhResult<Option<IcalVCalendar>> {
+  let event = khline.to_event()?;
+  let recurrence_id = event
+    .get_recurrence_id()
+    .ok_or("not an occurrence of a recurring event")?;
+
+  if following && cal.get_principal_khevent().get_start() == Some(recurrence_id.clone()) {
+    return Ok(None);
+  }
+
+  let cal = if following {
+    cal.with_recurrence_end(&recurrence_id)?
+  } else {
+    cal.with_exdate(&recurrence_id)?
+  };
+  Ok(Some(cal.with_last_modified_now().with_sequence_incremented()))
+}
+
+fn apply_deletion(operation: &mut Operation, deletion: &Deletion) -> KhResult<()> {
+  let khline = &deletion.khline;
+  match &deletion.remaining {
+    Some(cal) => {
+      operation.modified(&khline.path)?;
+      fileutil::write_cal(cal)?;
+      info!("deleted occurrences of {:#?}", khline.get_normalized_path());
+    }
+    None => {
+      operation.deleted(&khline.path)?;
+      remove_file(&khline.path)?;
+      info!("deleted {:#?}", khline.get_normalized_path());
+    }
+  }
   Ok(())
 }
 
-fn ask_really_delete(path: &PathBuf) -> bool {
+fn ask_really_delete(deletions: &[Deletion]) -> bool {
   if cfg!(test) { return true };
 
-  println!("Really delete {:#?}? y/n:", path);
+  if let [deletion] = deletions {
+    println!("Really delete {:#?}? y/n:", deletion.khline.path);
+  } else {
+    for deletion in deletions {
+      println!("{}", deletion.khline.get_normalized_path().display());
+    }
+    println!("Really delete {} events? y/n:", deletions.len());
+  }
 
-  match stdioutils::read_single_char_from_stdin().unwrap() {
-    'y' => true,
+  match stdioutils::read_single_char_from_terminal() {
+    Ok('y') => true,
     _ => false
   }
 }
@@ -43,26 +148,172 @@ fn ask_really_delete(path: &PathBuf) -> bool {
 mod tests {
   use super::*;
 
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Delete;
+  use crate::testdata;
   use crate::testutils::*;
   use assert_fs::prelude::*;
   use predicates::prelude::*;
+  use structopt::StructOpt;
+
+  fn read_cal(path: &str) -> String {
+    path.parse::<KhLine>().unwrap().to_cal().unwrap().to_string()
+  }
 
   #[test]
   fn test_do_delete_cursor() {
     let testdir = prepare_testdir("testdir_with_cursor");
 
-    do_delete().unwrap();
+    let args = CommandLine::from_iter(&["khaleesi", "delete"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
 
     let predicate = predicate::path::missing();
     testdir.child(".khaleesi/cal/twodaysacrossbuckets").assert(predicate);
     
   }
 
+  #[test]
+  fn test_do_delete_updates_index() {
+    let testdir = prepare_testdir("testdir_with_cursor");
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+    testdir.child(".khaleesi/index/2018-W50").assert(predicate::path::exists());
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    testdir.child(".khaleesi/index/2018-W50").assert(predicate::path::missing());
+    testdir.child(".khaleesi/index/2018-W51").assert(predicate::path::missing());
+  }
+
   #[test]
   #[should_panic]
   fn test_do_delete_no_cursor() {
     let _testdir = prepare_testdir("testdir");
 
-    do_delete().unwrap();
+    let args = CommandLine::from_iter(&["khaleesi", "delete"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+  }
+
+  #[test]
+  fn test_do_delete_this_occurrence() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur_timed");
+    stdioutils::test_stdin_write("1540195200 standup.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--this"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    let content = read_cal("standup.ics");
+    assert!(content.contains("EXDATE;TZID=Europe/Berlin:20181022T100000\r\n"));
+    assert!(content.contains("SEQUENCE:1\r\n"));
+    assert!(content.contains("RRULE:FREQ=WEEKLY;COUNT=5\r\n"));
+  }
+
+  #[test]
+  fn test_do_delete_this_occurrence_allday() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur");
+    stdioutils::test_stdin_write("1539813600 weekly.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--this"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    assert!(read_cal("weekly.ics").contains("EXDATE;VALUE=DATE:20181018\r\n"));
+  }
+
+  #[test]
+  fn test_do_delete_following() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur_timed");
+    stdioutils::test_stdin_write("1540803600 standup.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--following"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    assert!(read_cal("standup.ics").contains("RRULE:FREQ=WEEKLY;UNTIL=20181029T085959Z\r\n"));
+  }
+
+  #[test]
+  fn test_do_delete_following_first() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir_with_recur_timed");
+    stdioutils::test_stdin_write("1539590400 standup.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--following"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    testdir.child(".khaleesi/cal/standup.ics").assert(predicate::path::missing());
+  }
+
+  #[test]
+  fn test_do_delete_this_not_recurring() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--this"]);
+    if let Delete(x) = args.cmd {
+      assert!(do_delete(&x).is_err());
+    }
+  }
+
+  #[test]
+  fn test_delete_args_this_conflicts_with_following() {
+    assert!(CommandLine::from_iter_safe(&["khaleesi", "delete", "--this", "--following"]).is_err());
+  }
+
+  #[test]
+  fn test_do_delete_batch() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics\nsecond/second_sub/twodaysacrossbuckets.ics\nfirst/twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    testdir.child(".khaleesi/cal/first/twodaysacrossbuckets.ics").assert(predicate::path::missing());
+    testdir.child(".khaleesi/cal/second/second_sub/twodaysacrossbuckets.ics").assert(predicate::path::missing());
+  }
+
+  #[test]
+  fn test_do_delete_batch_occurrences() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur_timed");
+    stdioutils::test_stdin_write("1540195200 standup.ics\n1540803600 standup.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--this"]);
+    if let Delete(x) = args.cmd {
+      do_delete(&x).unwrap();
+    }
+
+    let content = read_cal("standup.ics");
+    assert!(content.contains("EXDATE;TZID=Europe/Berlin:20181022T100000\r\n"));
+    assert!(content.contains("EXDATE;TZID=Europe/Berlin:20181029T100000\r\n"));
+  }
+
+  #[test]
+  fn test_do_delete_batch_invalid() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("1544740200 first/twodaysacrossbuckets.ics\n1544740200 second/second_sub/twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--this"]);
+    if let Delete(x) = args.cmd {
+      assert!(do_delete(&x).is_err());
+    }
+    testdir.child(".khaleesi/cal/first/twodaysacrossbuckets.ics").assert(predicate::path::exists());
   }
 }
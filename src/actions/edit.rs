@@ -1,39 +1,149 @@
+use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
-use crate::backup::backup;
+use crate::actions::index;
 use crate::edit;
+use crate::icalwrap::{IcalTime, IcalVCalendar};
 use crate::input;
+use crate::journal::Operation;
 use crate::khline::KhLine;
-use crate::utils::fileutil;
+use crate::utils::{fileutil, misc};
 use crate::KhResult;
+use structopt::StructOpt;
 
-pub fn do_edit() -> KhResult<()> {
-  let khline = input::default_input_khline()?;
-  edit(&khline)
+#[derive(Debug, StructOpt)]
+pub struct EditArgs {
+  /// only edit this occurrence of a recurring event
+  #[structopt(long = "this", conflicts_with = "following")]
+  pub this: bool,
+  /// edit this and all following occurrences of a recurring event, as a new series
+  #[structopt(long = "following")]
+  pub following: bool,
 }
 
-fn edit(khline: &KhLine) -> KhResult<()> {
-  let tempfile = NamedTempFile::new()?;
+/// Edits every event in the input. The whole batch is one operation in the
+/// journal, so a single undo reverts all of it.
+pub fn do_edit(args: &EditArgs) -> KhResult<()> {
+  let khlines = input::default_input_khlines_or_cursor()?;
+
+  let mut operation = Operation::begin("edit")?;
+  let mut paths = Vec::new();
+  let result = khlines.iter().try_for_each(|khline| {
+    if args.this {
+      edit_occurrence(&mut operation, &mut paths, khline)
+    } else if args.following {
+      edit_following(&mut operation, &mut paths, khline)
+    } else {
+      edit(&mut operation, &mut paths, khline)
+    }
+  });
+  operation.commit()?;
+
+  index::update_index_or_warn(&paths);
+
+  result
+}
+
+fn edit(operation: &mut Operation, paths: &mut Vec<PathBuf>, khline: &KhLine) -> KhResult<()> {
   let calendar = khline.to_cal()?;
+  edit_calendar(operation, paths, khline, &calendar)
+}
 
-  fileutil::write_file(tempfile.path(), &calendar.to_string())?;
-  edit::edit_loop(&tempfile.path())?;
+/// Adds an override for the occurrence in `khline` to the file, and edits it.
+fn edit_occurrence(operation: &mut Operation, paths: &mut Vec<PathBuf>, khline: &KhLine) -> KhResult<()> {
+  let recurrence_id = get_recurrence_id(khline)?;
+  let calendar = khline.to_cal()?.with_recurrence_override(&recurrence_id)?;
+  edit_calendar(operation, paths, khline, &calendar)
+}
 
-  let backup_path = backup(&khline).unwrap();
-  info!("Backup written to {}", backup_path.display());
+/// Ends the series in `khline` before its occurrence, and edits the
+/// remaining occurrences as a new series with its own UID.
+fn edit_following(operation: &mut Operation, paths: &mut Vec<PathBuf>, khline: &KhLine) -> KhResult<()> {
+  let recurrence_id = get_recurrence_id(khline)?;
+  let calendar = khline.to_cal()?;
+  if calendar.get_principal_khevent().get_start() == Some(recurrence_id.clone()) {
+    return edit_calendar(operation, paths, khline, &calendar);
+  }
 
-  let edited_cal = KhLine::new(tempfile.path(), None).to_cal()?.with_dtstamp_now().with_last_modified_now();
+  let new_series = calendar
+    .clone()
+    .with_uid(&misc::make_new_uid())?
+    .with_recurrence_start(&recurrence_id)?;
+  let new_path = new_series.get_path().ok_or("calendar has no path")?.clone();
+  let new_series = edit_in_tempfile(&new_series)?;
+
+  let master = calendar
+    .with_recurrence_end(&recurrence_id)?
+    .with_last_modified_now()
+    .with_sequence_incremented();
+
+  operation.modified(&khline.path)?;
+  operation.created(&new_path);
+  paths.push(khline.path.clone());
+  paths.push(new_path.clone());
+  fileutil::write_cal(&master)?;
+  fileutil::write_file(&new_path, &new_series.to_string())?;
+  info!("Split series into {} and {}", khline.path.display(), new_path.display());
+
+  Ok(())
+}
+
+fn get_recurrence_id(khline: &KhLine) -> KhResult<IcalTime> {
+  let recurrence_id = khline
+    .to_event()?
+    .get_recurrence_id()
+    .ok_or("not an occurrence of a recurring event")?;
+  Ok(recurrence_id)
+}
+
+fn edit_calendar(
+  operation: &mut Operation,
+  paths: &mut Vec<PathBuf>,
+  khline: &KhLine,
+  calendar: &IcalVCalendar,
+) -> KhResult<()> {
+  let edited_cal = edit_in_tempfile(calendar)?;
+
+  operation.modified(&khline.path)?;
+  paths.push(khline.path.clone());
   fileutil::write_file(&khline.path, &edited_cal.to_string())?;
   info!("Successfully edited file {}", khline.path.display());
 
   Ok(())
 }
 
+fn edit_in_tempfile(calendar: &IcalVCalendar) -> KhResult<IcalVCalendar> {
+  let tempfile = NamedTempFile::new()?;
+
+  fileutil::write_file(tempfile.path(), &calendar.to_string())?;
+  edit::edit_loop(&tempfile.path())?;
+
+  let edited_cal = KhLine::new(tempfile.path(), None).to_cal()?.with_dtstamp_now().with_last_modified_now();
+  Ok(edited_cal)
+}
+
 #[cfg(test)]
 mod integration {
   use super::*;
 
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Edit;
+  use crate::journal;
+  use crate::testdata;
   use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+  use structopt::StructOpt;
+
+  type EditFn = fn(&mut Operation, &mut Vec<PathBuf>, &KhLine) -> KhResult<()>;
+
+  fn in_operation(edit_fn: EditFn, khline: &KhLine) -> KhResult<()> {
+    let mut operation = Operation::begin("edit").unwrap();
+    let result = edit_fn(&mut operation, &mut Vec::new(), khline);
+    operation.commit().unwrap();
+    result
+  }
 
   #[test]
   fn edit_test() {
@@ -41,10 +151,79 @@ mod integration {
 
     let khline = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap();
 
-    assert!(edit(&khline).is_ok());
+    assert!(in_operation(edit, &khline).is_ok());
     let event = khline.to_event().unwrap();
 
     assert_eq!("20130101T010203Z", event.get_dtstamp().unwrap());
     assert_eq!("20130101T010203Z", event.get_last_modified().unwrap());
   }
+
+  #[test]
+  fn edit_test_batch() {
+    let _testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics\nsecond/second_sub/twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "edit"]);
+    if let Edit(x) = args.cmd {
+      do_edit(&x).unwrap();
+    }
+
+    for path in &["first/twodaysacrossbuckets.ics", "second/second_sub/twodaysacrossbuckets.ics"] {
+      let event = path.parse::<KhLine>().unwrap().to_event().unwrap();
+      assert_eq!("20130101T010203Z", event.get_last_modified().unwrap());
+    }
+
+    let operations = journal::read_journal().unwrap();
+    assert_eq!(1, operations.len());
+    assert_eq!(2, operations[0].changes.len());
+  }
+
+  #[test]
+  fn edit_occurrence_test() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur_timed");
+
+    let khline = "1540195200 standup.ics".parse::<KhLine>().unwrap();
+
+    in_operation(edit_occurrence, &khline).unwrap();
+    // a second edit of the same occurrence reuses the override
+    in_operation(edit_occurrence, &khline).unwrap();
+
+    let cal = khline.to_cal().unwrap();
+    assert_eq!(2, cal.events_iter().count());
+    let content = cal.to_string();
+    assert!(content.contains("RECURRENCE-ID;TZID=Europe/Berlin:20181022T100000\r\n"));
+    assert!(content.contains("DTSTART;TZID=Europe/Berlin:20181022T100000\r\n"));
+    assert!(content.contains("DTEND;TZID=Europe/Berlin:20181022T101500\r\n"));
+    assert!(content.contains("RRULE:FREQ=WEEKLY;COUNT=5\r\n"));
+  }
+
+  #[test]
+  fn edit_following_test() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir_with_recur_timed");
+
+    let khline = "1540803600 standup.ics".parse::<KhLine>().unwrap();
+
+    in_operation(edit_following, &khline).unwrap();
+
+    let master = khline.to_cal().unwrap().to_string();
+    assert!(master.contains("RRULE:FREQ=WEEKLY;UNTIL=20181029T085959Z\r\n"));
+    assert!(master.contains("SEQUENCE:1\r\n"));
+
+    testdir.child(".khaleesi/cal/11111111-2222-3333-4444-444444444444@khaleesi.ics").assert(predicate::path::exists());
+    let new_series = "11111111-2222-3333-4444-444444444444@khaleesi.ics".parse::<KhLine>().unwrap().to_cal().unwrap().to_string();
+    assert!(new_series.contains("DTSTART;TZID=Europe/Berlin:20181029T100000\r\n"));
+    assert!(new_series.contains("DTEND;TZID=Europe/Berlin:20181029T101500\r\n"));
+    assert!(new_series.contains("RRULE:FREQ=WEEKLY;COUNT=3\r\n"));
+  }
+
+  #[test]
+  fn edit_occurrence_not_recurring_test() {
+    let _testdir = prepare_testdir("testdir");
+
+    let khline = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap();
+
+    assert!(in_operation(edit_occurrence, &khline).is_err());
+  }
 }
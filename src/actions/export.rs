@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use crate::icalwrap::IcalVCalendar;
+use crate::input;
+use crate::khline::KhLine;
+use crate::utils::fileutil;
+use crate::KhResult;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ExportArgs {
+  /// write to this file instead of stdout
+  #[structopt(short = "o", long = "output", parse(from_os_str))]
+  pub output: Option<PathBuf>,
+  /// export only the selected occurrences of recurring events, as single events
+  #[structopt(long = "flatten")]
+  pub flatten: bool,
+}
+
+pub fn do_export(args: &ExportArgs) -> KhResult<()> {
+  info!("do_export");
+
+  let khlines = input::default_input_khlines()?;
+
+  let mut exported = Vec::new();
+  let mut export_cal = IcalVCalendar::from_str(TEMPLATE_EXPORT, None)?;
+  for khline in khlines {
+    // a series is exported once, no matter how many of its occurrences are selected
+    let key = if args.flatten { khline.to_string() } else { khline.get_normalized_path().to_string_lossy().into_owned() };
+    if exported.contains(&key) {
+      continue;
+    }
+
+    let cal = if args.flatten { flatten(&khline)? } else { khline.to_cal()? };
+    export_cal = export_cal.with_components_from(&cal);
+    exported.push(key);
+  }
+
+  match &args.output {
+    Some(path) => {
+      fileutil::write_file(path, &export_cal.to_string())?;
+      info!("Exported {} events to {}", exported.len(), path.display());
+    }
+    None => khprintln!("{}", export_cal.to_string()),
+  }
+
+  Ok(())
+}
+
+fn flatten(khline: &KhLine) -> KhResult<IcalVCalendar> {
+  let cal = khline.to_cal()?;
+  match khline.to_event()?.get_recurrence_id() {
+    Some(recurrence_id) => Ok(cal.with_occurrence_flattened(&recurrence_id)?),
+    None => Ok(cal),
+  }
+}
+
+static TEMPLATE_EXPORT: &str = indoc!(
+  "
+  BEGIN:VCALENDAR
+  VERSION:2.0
+  PRODID:-//khaleesi //EN
+  END:VCALENDAR
+"
+);
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Export;
+  use crate::testdata;
+  use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+  use structopt::StructOpt;
+
+  #[test]
+  fn test_export() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir_with_recur_timed");
+    let standup = fileutil::read_file_to_string(testdir.child(".khaleesi/cal/standup.ics").path()).unwrap();
+    testdir.child(".khaleesi/cal/review.ics").write_str(&standup.replace("weeklystandup", "weeklyreview")).unwrap();
+    stdioutils::test_stdin_write("1540195200 standup.ics\n1540803600 standup.ics\n1540195200 review.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "export"]);
+    if let Export(x) = args.cmd {
+      do_export(&x).unwrap();
+    }
+
+    let stdout = stdioutils::test_stdout_clear();
+    assert_eq!(1, stdout.matches("BEGIN:VTIMEZONE").count());
+    assert_eq!(2, stdout.matches("BEGIN:VEVENT").count());
+    assert_eq!(2, stdout.matches("RRULE:FREQ=WEEKLY;COUNT=5").count());
+    assert!(stdout.contains("UID:weeklyreview"));
+  }
+
+  #[test]
+  fn test_export_flatten() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur_timed");
+    stdioutils::test_stdin_write("1540195200 standup.ics\n1540803600 standup.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "export", "--flatten"]);
+    if let Export(x) = args.cmd {
+      do_export(&x).unwrap();
+    }
+
+    let stdout = stdioutils::test_stdout_clear();
+    assert_eq!(2, stdout.matches("BEGIN:VEVENT").count());
+    assert!(!stdout.contains("RRULE"));
+    assert!(stdout.contains("UID:weeklystandup-20181022T100000"));
+    assert!(stdout.contains("DTSTART;TZID=Europe/Berlin:20181029T100000"));
+  }
+
+  #[test]
+  fn test_export_to_file() {
+    let testdir = prepare_testdir("testdir");
+    let output = testdir.child("export.ics");
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "export", "--output", output.path().to_str().unwrap()]);
+    if let Export(x) = args.cmd {
+      do_export(&x).unwrap();
+    }
+
+    output.assert(predicate::str::contains("PRODID:-//khaleesi //EN"));
+    output.assert(predicate::str::contains("UID:20181129T142636Z-11617-1000-1-0@pool-4-4"));
+    assert_eq!("", stdioutils::test_stdout_clear());
+  }
+}
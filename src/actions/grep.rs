@@ -0,0 +1,102 @@
+use yansi::Style;
+
+use crate::input;
+use crate::selectors::grep::{GrepFilter, GrepMatch};
+use crate::KhResult;
+use structopt::StructOpt;
+
+/// Number of characters shown on each side of a match
+const EXCERPT_CONTEXT: usize = 30;
+
+#[derive(Debug, StructOpt)]
+pub struct GrepArgs {
+  /// the terms to search for, prefixed with re:, ~ or fold: for regex, whole word or accent insensitive matching
+  #[structopt(name = "terms", raw(required = "true"))]
+  pub terms: Vec<String>,
+  /// only search in this property, can be given multiple times (default: summary, description, location)
+  #[structopt(short = "p", long = "property", raw(number_of_values = "1"))]
+  pub properties: Vec<String>,
+}
+
+pub fn action_grep(args: &GrepArgs) -> KhResult<()> {
+  let lines = input::default_input_khlines()?;
+  let filter = GrepFilter::new(&args.terms, &args.properties)?;
+
+  for khline in lines {
+    let event = match khline.to_event() {
+      Ok(event) => event,
+      Err(cause) => { warn!("{}", cause); continue },
+    };
+    let matches = filter.find_matches(&event);
+    if matches.is_empty() {
+      continue;
+    }
+    khprintln!("{}", khline);
+    for grep_match in matches {
+      khprintln!("    {}: {}", grep_match.property, excerpt(&grep_match));
+    }
+  }
+
+  Ok(())
+}
+
+fn excerpt(grep_match: &GrepMatch) -> String {
+  let value = &grep_match.value;
+  let before: String = value[..grep_match.start].chars().rev().take(EXCERPT_CONTEXT).collect::<Vec<char>>().into_iter().rev().collect();
+  let after: String = value[grep_match.end..].chars().take(EXCERPT_CONTEXT).collect();
+  let ellipsis_before = if before.len() < grep_match.start { "..." } else { "" };
+  let ellipsis_after = if grep_match.end + after.len() < value.len() { "..." } else { "" };
+
+  let style_match = Style::default().bold();
+  let excerpt = format!(
+    "{}{}{}{}{}",
+    ellipsis_before,
+    before,
+    style_match.paint(&value[grep_match.start..grep_match.end]),
+    after,
+    ellipsis_after
+  );
+  excerpt.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::testdata;
+  use crate::testutils;
+  use crate::utils::stdioutils;
+
+  #[test]
+  fn test_grep() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = GrepArgs { terms: vec!("jazz".to_string()), properties: vec!() };
+    action_grep(&args).unwrap();
+
+    let expected = "1182988800 rfc_multi_day_allday.ics\n    SUMMARY: Festival International de Jazz de Montreal\n";
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_grep_property() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = GrepArgs { terms: vec!("jazz".to_string()), properties: vec!("location".to_string()) };
+    action_grep(&args).unwrap();
+
+    assert_eq!("", stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_excerpt() {
+    testdata::setup();
+    let value = "a rather long description that mentions the jazz festival somewhere in the middle of it".to_string();
+    let start = value.find("jazz").unwrap();
+    let grep_match = GrepMatch { property: "DESCRIPTION".to_string(), value, start, end: start + 4 };
+
+    assert_eq!("...description that mentions the jazz festival somewhere in the mid...", excerpt(&grep_match));
+  }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::actions::index;
+use crate::calendars;
+use crate::defaults;
+use crate::icalwrap::IcalVCalendar;
+use crate::journal::Operation;
+use crate::khline::KhLine;
+use crate::utils::{fileutil, stdioutils};
+use crate::KhResult;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct ImportArgs {
+  /// the calendar to import the events into
+  #[structopt(name = "calendar")]
+  pub calendar: String,
+  /// the file to import, or - for stdin (default)
+  #[structopt(name = "file", parse(from_os_str))]
+  pub file: Option<PathBuf>,
+}
+
+pub fn do_import(args: &ImportArgs) -> KhResult<()> {
+  info!("do_import");
+
+  if !calendars::calendar_list().contains(&args.calendar) {
+    Err(format!("calendar {} does not exist", args.calendar))?
+  }
+
+  let content = match &args.file {
+    Some(path) if path != Path::new("-") => fileutil::read_file_to_string(path)?,
+    _ => stdioutils::read_lines_from_stdin()?.join("\n"),
+  };
+  let cal = IcalVCalendar::from_str(&content, None)?;
+
+  let calendar_dir = defaults::get_caldir().join(&args.calendar);
+  let existing = existing_uids(&calendar_dir);
+
+  let mut operation = Operation::begin("import")?;
+  let mut paths = Vec::new();
+  let result = cal.get_event_uids()?.iter().try_for_each(|uid| -> KhResult<()> {
+    let new_cal = cal.clone().with_keep_uid(uid).with_unused_timezones_removed();
+    match existing.get(uid) {
+      Some((path, sequence)) if new_cal.get_sequence() <= *sequence => {
+        warn!("Skipping {}, already in {} with the same or a newer SEQUENCE", uid, path.display());
+        return Ok(());
+      }
+      Some((path, _)) => {
+        warn!("Updating {} in {}", uid, path.display());
+        operation.modified(path)?;
+        write_imported(new_cal.with_path(path), &mut paths)?;
+      }
+      None => {
+        let path = calendar_dir.join(filename_for_uid(uid)?);
+        if path.exists() {
+          Err(format!("file {} already exists", path.display()))?
+        }
+        operation.created(&path);
+        write_imported(new_cal.with_path(&path), &mut paths)?;
+      }
+    }
+    Ok(())
+  });
+  operation.commit()?;
+
+  index::update_index_or_warn(&paths);
+
+  result
+}
+
+fn write_imported(cal: IcalVCalendar, paths: &mut Vec<PathBuf>) -> KhResult<()> {
+  paths.push(cal.get_path().ok_or("calendar has no path")?.clone());
+  fileutil::write_cal(&cal)?;
+  khprintln!("{}", KhLine::from(&cal));
+  Ok(())
+}
+
+/// A file name for the event with `uid`. UIDs come from the imported file, so
+/// anything that would point outside the calendar dir is replaced.
+fn filename_for_uid(uid: &str) -> KhResult<String> {
+  let name = uid.replace(|c: char| c == '/' || c == '\\', "_");
+  let name = name.trim_start_matches('.');
+  if name.is_empty() {
+    Err(format!("can not make a file name for UID {}", uid))?
+  }
+  Ok(name.to_string() + ".ics")
+}
+
+/// The path and SEQUENCE of every event in `calendar_dir`, by UID.
+fn existing_uids(calendar_dir: &Path) -> HashMap<String, (PathBuf, i32)> {
+  fileutil::file_iter(calendar_dir)
+    .filter(|path| path.extension().map_or(false, |extension| extension == "ics"))
+    .filter_map(|path| KhLine::new(&path, None).to_cal().ok())
+    .filter_map(|cal| {
+      let path = cal.get_path()?.clone();
+      Some((cal.get_uid(), (path, cal.get_sequence())))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Import;
+  use crate::testdata;
+  use crate::testutils::prepare_testdir;
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+  use structopt::StructOpt;
+
+  #[test]
+  fn test_import() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write(testdata::TEST_IMPORT_MULTIPLE);
+
+    let args = CommandLine::from_iter(&["khaleesi", "import", "first"]);
+    if let Import(x) = args.cmd {
+      do_import(&x).unwrap();
+    }
+
+    let berlin = testdir.child(".khaleesi/cal/first/import-berlin.ics");
+    berlin.assert(predicate::str::contains("RECURRENCE-ID;TZID=Europe/Berlin:20190114T100000"));
+    berlin.assert(predicate::str::contains("TZID:America/New_York").not());
+    let newyork = testdir.child(".khaleesi/cal/first/import-newyork.ics");
+    newyork.assert(predicate::str::contains("SUMMARY:Meeting in New York"));
+    newyork.assert(predicate::str::contains("UID:import-berlin").not());
+
+    assert_eq!(
+      "1546851600 first/import-berlin.ics\n1546956000 first/import-newyork.ics\n",
+      stdioutils::test_stdout_clear()
+    );
+  }
+
+  #[test]
+  fn test_import_file() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    let file = testdir.child("import.ics");
+    file.write_str(testdata::TEST_IMPORT_MULTIPLE).unwrap();
+
+    let args = CommandLine::from_iter(&["khaleesi", "import", "second", file.path().to_str().unwrap()]);
+    if let Import(x) = args.cmd {
+      do_import(&x).unwrap();
+    }
+
+    testdir.child(".khaleesi/cal/second/import-berlin.ics").assert(predicate::path::exists());
+    testdir.child(".khaleesi/cal/second/import-newyork.ics").assert(predicate::path::exists());
+  }
+
+  #[test]
+  fn test_import_duplicates() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write(testdata::TEST_IMPORT_MULTIPLE);
+    let args = CommandLine::from_iter(&["khaleesi", "import", "first"]);
+    if let Import(x) = args.cmd {
+      do_import(&x).unwrap();
+    }
+    stdioutils::test_stdout_clear();
+
+    stdioutils::test_stdin_write(&testdata::TEST_IMPORT_MULTIPLE.replace("SEQUENCE:2", "SEQUENCE:3"));
+    let args = CommandLine::from_iter(&["khaleesi", "import", "first"]);
+    if let Import(x) = args.cmd {
+      do_import(&x).unwrap();
+    }
+
+    assert_eq!("1546956000 first/import-newyork.ics\n", stdioutils::test_stdout_clear());
+    testdir.child(".khaleesi/cal/first/import-newyork.ics").assert(predicate::str::contains("SEQUENCE:3"));
+  }
+
+  #[test]
+  fn test_import_uid_with_path() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write(&testdata::TEST_IMPORT_MULTIPLE.replace("UID:import-newyork", "UID:../escape"));
+
+    let args = CommandLine::from_iter(&["khaleesi", "import", "first"]);
+    if let Import(x) = args.cmd {
+      do_import(&x).unwrap();
+    }
+
+    testdir.child(".khaleesi/cal/escape.ics").assert(predicate::path::missing());
+    testdir.child(".khaleesi/cal/first/_escape.ics").assert(predicate::str::contains("UID:../escape"));
+  }
+
+  #[test]
+  fn test_filename_for_uid() {
+    assert_eq!("abc@example.com.ics", filename_for_uid("abc@example.com").unwrap());
+    assert_eq!("_etc_passwd.ics", filename_for_uid("/etc/passwd").unwrap());
+    assert_eq!("_escape.ics", filename_for_uid("..\\escape").unwrap());
+    assert!(filename_for_uid("..").is_err());
+  }
+
+  #[test]
+  fn test_import_unknown_calendar() {
+    let _testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write(testdata::TEST_IMPORT_MULTIPLE);
+
+    let args = CommandLine::from_iter(&["khaleesi", "import", "third"]);
+    if let Import(x) = args.cmd {
+      assert!(do_import(&x).is_err());
+    }
+  }
+}
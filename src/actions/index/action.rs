@@ -1,13 +1,14 @@
 use chrono::prelude::*;
 use crate::icalwrap::*;
-use std::collections::HashMap;
+use std::collections::{HashMap,HashSet};
 use std::fs;
 use std::path::{Path,PathBuf};
 use std::time::SystemTime;
-use walkdir::DirEntry;
 
 use crate::defaults::*;
 use super::{IndexArgs, indextime};
+use super::bucketable::Merge;
+use super::manifest::{IndexManifest,ManifestEntry};
 use crate::utils::fileutil;
 use crate::utils::lock;
 use crate::utils::misc;
@@ -23,18 +24,6 @@ pub fn action_index(args: &IndexArgs) -> KhResult<()> {
   index_dir(&indexpath, reindex)
 }
 
-fn add_buckets_for_calendar(buckets: &mut HashMap<String, Vec<String>>, cal: &IcalVCalendar) {
-  use super::bucketable::Bucketable;
-  use super::bucketable::Merge;
-
-  match cal.get_buckets() {
-    Ok(cal_buckets) => buckets.merge(cal_buckets),
-    Err(error) => {
-      warn!("{}", error)
-    }
-  }
-}
-
 fn index_dir(dir: &Path, reindex: bool) -> KhResult<()> {
   use std::time::Instant;
 
@@ -48,28 +37,57 @@ fn index_dir(dir: &Path, reindex: bool) -> KhResult<()> {
   let now = Instant::now();
   let start_time = Utc::now();
 
-  let last_index_time = if reindex {
+  let manifest = if reindex {
     debug!("Forced reindex, indexing all files");
     None
   } else {
-    let last_index_time = indextime::get_index_time();
-    match last_index_time {
-      Some(time) => debug!("Previously indexed {}, indexing newer files only", time.with_timezone(&Local)),
-        None => debug!("No previous index time, indexing all files"),
+    let manifest = IndexManifest::read();
+    match (&manifest, indextime::get_index_time()) {
+      (Some(_), Some(time)) => debug!("Previously indexed {}, indexing changed files only", time.with_timezone(&Local)),
+      (Some(_), None) => debug!("Indexing changed files only"),
+      (None, _) => debug!("No index manifest, indexing all files"),
     }
-    last_index_time
+    manifest
   };
 
-  let modified_since = last_index_time.map(|time| time.timestamp()).unwrap_or(0);
-  let ics_files = get_ics_files(dir, modified_since);
-
-  let buckets = read_buckets(ics_files);
-
   let indexdir = get_indexdir();
-  let clear_index_dir = last_index_time.is_none();
+  let clear_index_dir = manifest.is_none();
   prepare_index_dir(&indexdir, clear_index_dir)?;
 
-  write_index(&indexdir, &buckets);
+  let mut manifest = manifest.unwrap_or_default();
+  let mut stale_lines: HashMap<String, Vec<String>> = HashMap::new();
+  let mut fresh_lines: HashMap<String, Vec<String>> = HashMap::new();
+  let mut seen_files: HashSet<PathBuf> = HashSet::new();
+
+  let mut total_files = 0;
+  for file in get_ics_files(dir) {
+    let (mtime, size) = get_mtime_and_size(&file);
+    seen_files.insert(file.clone());
+    if manifest.get(&file).map_or(false, |entry| entry.mtime == mtime && entry.size == size) {
+      continue;
+    }
+
+    total_files += 1;
+    let buckets = read_buckets(&file);
+    let entry = ManifestEntry { mtime, size, buckets: buckets.clone() };
+    if let Some(old_entry) = manifest.insert(&file, entry) {
+      stale_lines.merge(old_entry.buckets);
+    }
+    fresh_lines.merge(buckets);
+  }
+
+  for file in manifest.paths_below(dir) {
+    if !seen_files.contains(&file) {
+      debug!("Removing deleted file from index: {:?}", file);
+      if let Some(old_entry) = manifest.remove(&file) {
+        stale_lines.merge(old_entry.buckets);
+      }
+    }
+  }
+  info!("Loaded {} changed files into {} buckets", total_files, fresh_lines.len());
+
+  write_index(&indexdir, &stale_lines, &fresh_lines)?;
+  manifest.write()?;
   info!("Index written in {}ms", misc::format_duration(&now.elapsed()));
 
   indextime::write_index_time(&start_time);
@@ -77,64 +95,138 @@ fn index_dir(dir: &Path, reindex: bool) -> KhResult<()> {
   Ok(())
 }
 
-pub fn get_ics_files(dir: &Path, modified_since: i64) -> impl Iterator<Item = PathBuf> {
+/// Brings the index entries of the given files up to date. Files that no
+/// longer exist are dropped from the index. Does nothing if there is no index yet.
+pub fn update_index(files: &[PathBuf]) -> KhResult<()> {
+  let _lock = lock::lock_file_exclusive(&get_indexlockfile())?;
+
+  let mut manifest = match IndexManifest::read() {
+    Some(manifest) => manifest,
+    None => {
+      debug!("No index manifest, skipping index update");
+      return Ok(());
+    }
+  };
+
+  let mut stale_lines: HashMap<String, Vec<String>> = HashMap::new();
+  let mut fresh_lines: HashMap<String, Vec<String>> = HashMap::new();
+  for file in files {
+    if let Some(old_entry) = manifest.remove(file) {
+      stale_lines.merge(old_entry.buckets);
+    }
+    if file.is_file() {
+      let buckets = read_buckets(file);
+      let (mtime, size) = get_mtime_and_size(file);
+      let entry = ManifestEntry { mtime, size, buckets: buckets.clone() };
+      manifest.insert(file, entry);
+      fresh_lines.merge(buckets);
+    }
+  }
+
+  write_index(&get_indexdir(), &stale_lines, &fresh_lines)?;
+  manifest.write()?;
+  debug!("Updated index for {} files", files.len());
+
+  Ok(())
+}
+
+/// Like `update_index`, but only warns if the index could not be updated.
+/// Used after an action has already written its changes.
+pub fn update_index_or_warn(files: &[PathBuf]) {
+  if let Err(error) = update_index(files) {
+    warn!("Could not update index: {}", error);
+  }
+}
+
+pub fn get_ics_files(dir: &Path) -> impl Iterator<Item = PathBuf> {
   use walkdir::WalkDir;
 
   WalkDir::new(dir)
     .follow_links(true)
     .into_iter()
-    .filter_entry(move |entry| accept_entry(entry, modified_since))
     .filter_map(|e| e.ok())
     .filter(|e| e.file_type().is_file())
     .filter(|e| e.path().extension().map_or(false, |extension| extension == "ics"))
     .map(|entry| entry.into_path())
 }
 
-fn accept_entry(dir_entry: &DirEntry, modified_since: i64) -> bool {
-  if dir_entry.path().is_dir() {
-    return true;
-  }
-  dir_entry.metadata()
-    .map_err(|err| err.into()) // transform to io::Error
-    .and_then(|metadata| metadata.modified())
-    .map(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).unwrap())
-    .map(|modified| modified.as_secs() as i64)
-    .map(|modified| modified > modified_since)
-    .unwrap_or(false)
+/// The mtime in nanoseconds and the size of a file. Either one changing means
+/// the file needs to be indexed again.
+fn get_mtime_and_size(path: &Path) -> (i64, u64) {
+  let metadata = match fs::metadata(path) {
+    Ok(metadata) => metadata,
+    Err(_) => return (0, 0),
+  };
+  let mtime = metadata
+    .modified()
+    .ok()
+    .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+    .map(|modified| modified.as_secs() as i64 * 1_000_000_000 + i64::from(modified.subsec_nanos()))
+    .unwrap_or(0);
+  (mtime, metadata.len())
 }
 
-fn read_buckets(ics_files: impl Iterator<Item = PathBuf>) -> HashMap<String, Vec<String>> {
-  let mut buckets: HashMap<String, Vec<String>> = HashMap::new();
+fn read_buckets(file: &Path) -> HashMap<String, Vec<String>> {
+  use super::bucketable::Bucketable;
 
-  let mut total_files = 0;
-  for file in ics_files {
-    debug!("Indexing file: {:?}", file);
-    match fileutil::read_file_to_string(&file) {
-      Ok(content) => {
-        total_files += 1;
-        match IcalVCalendar::from_str(&content, Some(&file)) {
-          Ok(cal) => add_buckets_for_calendar(&mut buckets, &cal),
-          Err(error) => error!("{:?}: {}", file, error)
-        }
-      }
-      Err(error) => error!("{}", error),
+  debug!("Indexing file: {:?}", file);
+  let content = match fileutil::read_file_to_string(file) {
+    Ok(content) => content,
+    Err(error) => {
+      error!("{}", error);
+      return HashMap::new();
+    }
+  };
+  let cal = match IcalVCalendar::from_str(&content, Some(file)) {
+    Ok(cal) => cal,
+    Err(error) => {
+      error!("{:?}: {}", file, error);
+      return HashMap::new();
+    }
+  };
+  match cal.get_buckets() {
+    Ok(buckets) => buckets,
+    Err(error) => {
+      warn!("{}", error);
+      HashMap::new()
     }
   }
-
-  info!("Loaded {} files into {} buckets", total_files, buckets.len());
-  buckets
 }
 
-fn write_index(index_dir: &Path, buckets: &HashMap<String, Vec<String>>) {
-  for (key, val) in buckets.iter() {
+/// Removes the stale lines from their bucket files and adds the fresh ones.
+/// Buckets that end up empty are deleted.
+fn write_index(index_dir: &Path, stale_lines: &HashMap<String, Vec<String>>, fresh_lines: &HashMap<String, Vec<String>>) -> KhResult<()> {
+  let keys: HashSet<&String> = stale_lines.keys().chain(fresh_lines.keys()).collect();
+  for key in keys {
     let bucketfile = bucket_file(index_dir, key);
     trace!("Writing bucket: {}", key);
-    let content = &[&val.join("\n"), "\n"].concat();
-    if let Err(error) = fileutil::append_file(&bucketfile, content) {
-      error!("{}", error);
-      return;
+
+    let mut lines: Vec<String> = if bucketfile.exists() {
+      fileutil::read_lines_from_file(&bucketfile)?.collect()
+    } else {
+      Vec::new()
+    };
+    if let Some(stale) = stale_lines.get(key) {
+      for line in stale {
+        if let Some(position) = lines.iter().position(|x| x == line) {
+          lines.remove(position);
+        }
+      }
+    }
+    if let Some(fresh) = fresh_lines.get(key) {
+      lines.extend(fresh.iter().cloned());
+    }
+
+    if lines.is_empty() {
+      if bucketfile.exists() {
+        fs::remove_file(&bucketfile)?;
+      }
+    } else {
+      let content = &[&lines.join("\n"), "\n"].concat();
+      fileutil::write_file(&bucketfile, content)?;
     }
   }
+  Ok(())
 }
 
 fn bucket_file(index_dir: &Path, key: &str) -> PathBuf {
@@ -163,6 +255,7 @@ mod integration {
 
   use crate::testutils::prepare_testdir;
   use assert_fs::prelude::*;
+  use predicates::prelude::*;
   use crate::cli::CommandLine;
   use crate::cli::Command::Index;
   use structopt::StructOpt;
@@ -179,4 +272,92 @@ mod integration {
     testdir.child(".khaleesi/index/2018-W50").assert("1544740200 twodaysacrossbuckets.ics\n");
     testdir.child(".khaleesi/index/2018-W51").assert("1544740200 twodaysacrossbuckets.ics\n");
   }
+
+  #[test]
+  fn test_index_incremental_edited() {
+    let testdir = prepare_testdir("testdir");
+    let event_path = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+
+    index_dir(&get_caldir(), false).unwrap();
+
+    let content = fileutil::read_file_to_string(event_path.path()).unwrap();
+    let content = content
+      .replace("20181213T233000", "20181220T233000")
+      .replace("20181217T193000", "20181221T193000");
+    fileutil::write_file(event_path.path(), &content).unwrap();
+    // pretend the file was indexed before its last change
+    let mut manifest = IndexManifest::read().unwrap();
+    let mut entry = manifest.get(event_path.path()).unwrap().clone();
+    entry.mtime = 0;
+    manifest.insert(event_path.path(), entry);
+    manifest.write().unwrap();
+
+    index_dir(&get_caldir(), false).unwrap();
+
+    testdir.child(".khaleesi/index/2018-W50").assert(predicate::path::missing());
+    testdir.child(".khaleesi/index/2018-W51").assert("1545345000 twodaysacrossbuckets.ics\n");
+  }
+
+  #[test]
+  fn test_index_incremental_edited_same_mtime() {
+    let testdir = prepare_testdir("testdir");
+    let event_path = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+
+    index_dir(&get_caldir(), false).unwrap();
+
+    let content = fileutil::read_file_to_string(event_path.path()).unwrap();
+    let content = content
+      .replace("20181213T233000", "20181220T233000")
+      .replace("20181217T193000", "20181221T193000")
+      .replace("SUMMARY:", "SUMMARY:Edited ");
+    fileutil::write_file(event_path.path(), &content).unwrap();
+    // pretend the edit happened within the same tick as the last index run
+    let mut manifest = IndexManifest::read().unwrap();
+    let mut entry = manifest.get(event_path.path()).unwrap().clone();
+    entry.mtime = get_mtime_and_size(event_path.path()).0;
+    manifest.insert(event_path.path(), entry);
+    manifest.write().unwrap();
+
+    index_dir(&get_caldir(), false).unwrap();
+
+    testdir.child(".khaleesi/index/2018-W50").assert(predicate::path::missing());
+    testdir.child(".khaleesi/index/2018-W51").assert("1545345000 twodaysacrossbuckets.ics\n");
+  }
+
+  #[test]
+  fn test_index_incremental_deleted() {
+    let testdir = prepare_testdir("testdir");
+
+    index_dir(&get_caldir(), false).unwrap();
+    fs::remove_file(testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics").path()).unwrap();
+    index_dir(&get_caldir(), false).unwrap();
+
+    testdir.child(".khaleesi/index/2018-W50").assert(predicate::path::missing());
+    testdir.child(".khaleesi/index/2018-W51").assert(predicate::path::missing());
+    assert!(IndexManifest::read().unwrap().paths_below(&get_caldir()).is_empty());
+  }
+
+  #[test]
+  fn test_update_index() {
+    let testdir = prepare_testdir("testdir");
+    let old_path = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+    let new_path = testdir.child(".khaleesi/cal/moved.ics");
+
+    index_dir(&get_caldir(), false).unwrap();
+    fs::rename(old_path.path(), new_path.path()).unwrap();
+    update_index(&[old_path.path().to_path_buf(), new_path.path().to_path_buf()]).unwrap();
+
+    testdir.child(".khaleesi/index/2018-W50").assert("1544740200 moved.ics\n");
+    testdir.child(".khaleesi/index/2018-W51").assert("1544740200 moved.ics\n");
+  }
+
+  #[test]
+  fn test_update_index_no_index() {
+    let testdir = prepare_testdir("testdir");
+    let path = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+
+    update_index(&[path.path().to_path_buf()]).unwrap();
+
+    testdir.child(".khaleesi/index").assert(predicate::path::missing());
+  }
 }
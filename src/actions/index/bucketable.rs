@@ -38,16 +38,19 @@ impl Bucketable for KhEvent {
       //end_date = end_date.pred();
     //}
 
-    let buckets = Self::buckets_for_interval(start_date, end_date);
     let khline = KhLine::from(self);
-    for bucketid in buckets {
-      result
-        .entry(bucketid)
-        .and_modify(|items| items.push(khline.to_string()))
-        .or_insert_with(|| vec!(khline.to_string()));
-    }
-
-    if self.is_recur_master() {
+    // the first occurrence of a recurring event may be excluded or overridden,
+    // so it only gets indexed as one of the instances
+    if !self.is_recur_master() {
+      let buckets = Self::buckets_for_interval(start_date, end_date);
+      for bucketid in buckets {
+        result
+          .entry(bucketid)
+          .and_modify(|items| items.push(khline.to_string()))
+          .or_insert_with(|| vec!(khline.to_string()));
+      }
+    } else {
+      result.insert(misc::RECUR_BUCKET.to_string(), vec!(khline.to_string()));
       for instance in self.get_recur_instances() {
         let recur_buckets = instance.get_buckets()?;
         result.merge(recur_buckets)
@@ -157,6 +160,56 @@ mod tests {
     assert_eq!(event_buckets, cal_buckets);
     let mut cal_bucket_names = cal_buckets.keys().collect::<Vec<&String>>();
     cal_bucket_names.sort_unstable();
-    assert_eq!(vec!("2018-W41", "2018-W42", "2018-W43", "2018-W44", "2018-W45", "2018-W46", "2018-W47", "2018-W48", "2018-W49", "2018-W50"), cal_bucket_names);
+    assert_eq!(vec!("2018-W41", "2018-W42", "2018-W43", "2018-W44", "2018-W45", "2018-W46", "2018-W47", "2018-W48", "2018-W49", "2018-W50", "recurring"), cal_bucket_names);
+  }
+
+  #[test]
+  fn buckets_recur_override() {
+    use crate::testdata;
+    use std::path::PathBuf;
+
+    testdata::setup();
+    let path = PathBuf::from("test/path");
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR_OVERRIDE, Some(&path)).unwrap();
+
+    let buckets = cal.get_buckets().unwrap();
+
+    let mut bucket_names = buckets.keys().collect::<Vec<&String>>();
+    bucket_names.sort_unstable();
+    assert_eq!(vec!("2018-W41", "2018-W42", "2018-W43", "recurring"), bucket_names);
+    // the override on friday replaces the occurrence on thursday
+    assert_eq!(&vec!("1539907200 test/path".to_string()), buckets.get("2018-W42").unwrap());
+    assert_eq!(&vec!("1540418400 test/path".to_string()), buckets.get("2018-W43").unwrap());
+  }
+
+  #[test]
+  fn buckets_recur_override_no_master() {
+    use crate::testutils::path_to;
+    use crate::utils::fileutil;
+
+    let path = path_to("khal/event_dt_recuid_no_master.ics");
+    let cal = IcalVCalendar::from_str(&fileutil::read_file_to_string(&path).unwrap(), Some(&path)).unwrap();
+
+    let buckets = cal.get_buckets().unwrap();
+
+    assert_eq!(vec!("2017-W13"), buckets.keys().collect::<Vec<&String>>());
+  }
+
+  #[test]
+  fn buckets_rdate() {
+    use crate::testdata;
+    use crate::testutils::path_to;
+    use crate::utils::fileutil;
+
+    testdata::setup();
+    let path = path_to("khal/event_d_rdate.ics");
+    let cal = IcalVCalendar::from_str(&fileutil::read_file_to_string(&path).unwrap(), Some(&path)).unwrap();
+
+    let buckets = cal.get_buckets().unwrap();
+
+    let mut bucket_names = buckets.keys().collect::<Vec<&String>>();
+    bucket_names.sort_unstable();
+    assert_eq!(vec!("2015-W33", "recurring"), bucket_names);
+    assert_eq!(4, buckets.get("2015-W33").unwrap().len());
   }
 }
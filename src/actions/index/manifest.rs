@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::path::{Path,PathBuf};
+
+use crate::defaults::*;
+use crate::utils::fileutil;
+use crate::KhResult;
+
+/// Remembers, for every indexed file, its mtime and the lines it contributed
+/// to each bucket, so that an incremental index run can take them out again.
+#[derive(Serialize,Deserialize,Debug,Default,PartialEq)]
+pub struct IndexManifest {
+  files: HashMap<String, ManifestEntry>,
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub struct ManifestEntry {
+  /// nanoseconds since the epoch, so that an edit within the same second as
+  /// the last index run is still noticed
+  pub mtime: i64,
+  #[serde(default)]
+  pub size: u64,
+  pub buckets: HashMap<String, Vec<String>>,
+}
+
+impl IndexManifest {
+  pub fn read() -> Option<Self> {
+    let content = fileutil::read_file_to_string(&get_indexmanifestfile()).ok()?;
+    match toml::from_str(&content) {
+      Ok(manifest) => Some(manifest),
+      Err(error) => {
+        warn!("Could not read index manifest: {}", error);
+        None
+      }
+    }
+  }
+
+  pub fn write(&self) -> KhResult<()> {
+    let content = toml::to_string(self).map_err(|err| err.to_string())?;
+    fileutil::write_file(&get_indexmanifestfile(), &content)?;
+    Ok(())
+  }
+
+  pub fn get(&self, path: &Path) -> Option<&ManifestEntry> {
+    self.files.get(&*path.to_string_lossy())
+  }
+
+  pub fn insert(&mut self, path: &Path, entry: ManifestEntry) -> Option<ManifestEntry> {
+    self.files.insert(path.to_string_lossy().into_owned(), entry)
+  }
+
+  pub fn remove(&mut self, path: &Path) -> Option<ManifestEntry> {
+    self.files.remove(&*path.to_string_lossy())
+  }
+
+  pub fn paths_below(&self, dir: &Path) -> Vec<PathBuf> {
+    self.files
+      .keys()
+      .map(PathBuf::from)
+      .filter(|path| path.starts_with(dir))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::testutils;
+
+  #[test]
+  fn test_write_read() {
+    let _testdir = testutils::prepare_testdir("testdir");
+
+    let path = PathBuf::from("/some/cal/event.ics");
+    let entry = ManifestEntry {
+      mtime: 1544740200,
+      size: 1024,
+      buckets: hashmap!{"2018-W50".to_string() => vec!("1544740200 cal/event.ics".to_string())},
+    };
+    let mut manifest = IndexManifest::default();
+    manifest.insert(&path, entry.clone());
+    manifest.write().unwrap();
+
+    let manifest = IndexManifest::read().unwrap();
+    assert_eq!(Some(&entry), manifest.get(&path));
+    assert_eq!(vec!(path), manifest.paths_below(&PathBuf::from("/some")));
+    assert!(manifest.paths_below(&PathBuf::from("/other")).is_empty());
+  }
+
+  #[test]
+  fn test_read_missing() {
+    let _testdir = testutils::prepare_testdir("testdir");
+
+    assert!(IndexManifest::read().is_none());
+  }
+}
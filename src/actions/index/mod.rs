@@ -1,8 +1,9 @@
 pub mod action;
 mod indextime;
 mod bucketable;
+mod manifest;
 
-pub use self::action::action_index;
+pub use self::action::{action_index, update_index, update_index_or_warn};
 
 use structopt::StructOpt;
 use std::path::PathBuf;
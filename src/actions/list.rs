@@ -1,5 +1,6 @@
 use crate::selectors::SelectFilters;
 use crate::input;
+use crate::output::{self, EventRecord, OutputArgs, OutputFormat};
 use crate::KhResult;
 use structopt::StructOpt;
 
@@ -8,25 +9,52 @@ pub struct ListArgs {
   /// the arguments for the selection
   #[structopt(name = "args")]
   pub args: Vec<String>,
+  #[structopt(flatten)]
+  pub output: OutputArgs,
 }
 
-pub fn list_by_args(args: &[&str]) -> KhResult<()> {
+pub fn list_by_args(args: &[&str], format: OutputFormat) -> KhResult<()> {
   let lines = input::default_input_khlines()?;
   let filters = SelectFilters::parse_from_args_with_range(args)?;
 
   let events = lines
     .enumerate()
-    .filter(|(index, khline)| {
+    .filter_map(|(index, khline)| {
       match khline.to_event() {
-        Ok(event) => filters.is_selected_index(*index, &event),
-        Err(cause) => { warn!("{}", cause); false },
+        Ok(event) => if filters.is_selected_index(index, &event) { Some((khline, event)) } else { None },
+        Err(cause) => { warn!("{}", cause); None },
       }
     });
 
-  for (_, khline) in events {
-    khprintln!("{}", khline);
+  if format == OutputFormat::Text {
+    for (khline, _) in events {
+      khprintln!("{}", khline);
+    }
+  } else {
+    let records: Vec<EventRecord> = events.map(|(_, event)| EventRecord::from(&event)).collect();
+    output::print_records(format, &records)?;
   }
 
   Ok(())
 }
 
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+
+  #[test]
+  fn test_list_jsonl() {
+    let _testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics\nsecond/second_sub/twodaysacrossbuckets.ics\n");
+
+    list_by_args(&[], OutputFormat::Jsonl).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    assert_eq!(2, stdout.lines().count());
+    assert!(stdout.lines().all(|line| line.starts_with(r#"{"path":""#)));
+    assert!(stdout.contains(r#""path":"second/second_sub/twodaysacrossbuckets.ics""#));
+  }
+}
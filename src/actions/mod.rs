@@ -1,16 +1,23 @@
 pub mod agenda;
+pub mod backup;
+pub mod calendar;
 pub mod get;
 pub mod copy;
 pub mod cursor;
 pub mod edit;
+pub mod export;
+pub mod import;
 pub mod index;
 pub mod list;
 pub mod modify;
+pub mod mv;
 pub mod delete;
 pub mod new;
 pub mod select;
 pub mod seq;
 pub mod show;
+pub mod sort;
 pub mod undo;
 pub mod unroll;
 pub mod gen_completions;
+pub mod grep;
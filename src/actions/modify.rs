@@ -1,5 +1,15 @@
-use crate::backup::backup;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+use chrono::Datelike;
+
+use crate::actions::index;
+use crate::cursorfile;
+use crate::icalwrap::{IcalDuration, IcalTime, IcalVCalendar};
 use crate::input;
+use crate::journal::Operation;
+use crate::khline::KhLine;
+use crate::utils::dateutil;
 use crate::utils::fileutil::write_cal;
 use crate::KhResult;
 use structopt::StructOpt;
@@ -19,33 +29,239 @@ pub enum ModifyCommand {
   /// Show agenda view
   #[structopt(name = "remove-xlicerror", author = "")]
   RemoveXlicerror,
+  /// Set a property to a value
+  #[structopt(name = "set", author = "")]
+  Set {
+    /// the property, e.g. SUMMARY or LOCATION
+    #[structopt(name = "property")]
+    property: String,
+    /// the new value
+    #[structopt(name = "value")]
+    value: String,
+  },
+  /// Remove a property
+  #[structopt(name = "unset", author = "")]
+  Unset {
+    /// the property, e.g. URL
+    #[structopt(name = "property")]
+    property: String,
+  },
+  /// Add a category
+  #[structopt(name = "add-category", author = "")]
+  AddCategory {
+    /// the category
+    #[structopt(name = "category")]
+    category: String,
+  },
+  /// Remove a category
+  #[structopt(name = "remove-category", author = "")]
+  RemoveCategory {
+    /// the category
+    #[structopt(name = "category")]
+    category: String,
+  },
+  /// Move events by an offset
+  #[structopt(name = "shift", author = "")]
+  Shift {
+    /// the offset, e.g. +1d, -30m or +1h30m
+    #[structopt(name = "offset", raw(allow_hyphen_values = "true"))]
+    offset: String,
+  },
+  /// Move events to a new start, keeping their duration
+  #[structopt(name = "move-to", author = "")]
+  MoveTo {
+    /// the new start, a local date-time like 2019-01-07T10:00 or a date for all-day events
+    #[structopt(name = "start")]
+    start: String,
+  },
 }
 
 pub fn do_modify(args: &ModifyArgs) -> KhResult<()> {
   info!("do_modify");
 
-  match &args.modify_cmd {
-    ModifyCommand::RemoveXlicerror => {
-      let dry_run = args.dry_run;
-
-      let khlines = input::default_input_khlines()?;
-      for khline in khlines {
-        let (cal, count_removed) = khline.to_cal()?.with_remove_property("X-LIC-ERROR");
-        if count_removed > 0 {
-          if !dry_run {
-            info!("Modifying {}", cal.get_path_as_string().unwrap());
-
-            let backup_path = backup(&khline).unwrap();
-            info!("Backup written to {}", backup_path.display());
-            write_cal(&cal)?
-          } else {
-            info!("Would modify {}", cal.get_path_as_string().unwrap());
-          };
+  let dry_run = args.dry_run;
+  let mut modified_paths: Vec<PathBuf> = Vec::new();
+  let cursor = cursorfile::read_cursorfile().ok();
+
+  let khlines = input::default_input_khlines()?;
+  let mut operation = if dry_run { None } else { Some(Operation::begin("modify")?) };
+  let result = khlines.iter().try_for_each(|khline| -> KhResult<()> {
+    let cal = khline.to_cal()?;
+    let (cal, changed) = match &args.modify_cmd {
+      ModifyCommand::RemoveXlicerror => {
+        let (cal, count_removed) = cal.with_remove_property("X-LIC-ERROR");
+        (cal, count_removed > 0)
+      }
+      command => {
+        let (cal, changed) = modify_properties(cal, command)?;
+        if changed {
+          (cal.with_last_modified_now().with_sequence_incremented(), true)
+        } else {
+          (cal, false)
         }
       }
+    };
+
+    if changed {
+      if let Some(operation) = operation.as_mut() {
+        modified_paths.push(khline.get_path().to_path_buf());
+        write_modified(operation, khline, &cal)?;
+        if is_reschedule(&args.modify_cmd) {
+          let new_khline = KhLine::from(&cal);
+          if cursor.as_ref().map_or(false, |cursor| cursor.get_path() == khline.get_path()) {
+            cursorfile::write_cursorfile(&new_khline.to_string())?;
+          }
+          khprintln!("{}", new_khline);
+        }
+      } else {
+        info!("Would modify {}", cal.get_path_as_string().unwrap());
+      };
+    }
+    Ok(())
+  });
+
+  if let Some(operation) = operation {
+    operation.commit()?;
+  }
+
+  if !modified_paths.is_empty() {
+    index::update_index_or_warn(&modified_paths);
+  }
+
+  result
+}
+
+fn modify_properties(cal: IcalVCalendar, command: &ModifyCommand) -> KhResult<(IcalVCalendar, bool)> {
+  let result = match command {
+    ModifyCommand::RemoveXlicerror => (cal, false),
+    ModifyCommand::Set { property, value } => {
+      if property.eq_ignore_ascii_case("UID") {
+        Err("UID can not be modified")?
+      }
+      (cal.with_property(property, value)?, true)
+    }
+    ModifyCommand::Unset { property } => {
+      if ["UID", "DTSTART"].iter().any(|required| property.eq_ignore_ascii_case(required)) {
+        Err(format!("{} can not be removed", property.to_uppercase()))?
+      }
+      let (cal, count_removed) = cal.with_unset_property(property);
+      (cal, count_removed > 0)
     }
+    ModifyCommand::AddCategory { category } => {
+      let mut categories = cal.get_categories();
+      if categories.iter().any(|existing| existing.eq_ignore_ascii_case(category)) {
+        (cal, false)
+      } else {
+        categories.push(category.to_string());
+        (cal.with_categories(&categories), true)
+      }
+    }
+    ModifyCommand::Shift { offset } => {
+      let seconds = dateutil::seconds_from_duration_str(offset)?;
+      (shift_event(cal, seconds)?, true)
+    }
+    ModifyCommand::MoveTo { start } => {
+      let new_start = parse_new_start(start)?;
+      (move_event(cal, &new_start)?, true)
+    }
+    ModifyCommand::RemoveCategory { category } => {
+      let categories = cal.get_categories();
+      let remaining: Vec<String> = categories
+        .iter()
+        .filter(|existing| !existing.eq_ignore_ascii_case(category))
+        .cloned()
+        .collect();
+      if remaining.len() == categories.len() {
+        (cal, false)
+      } else {
+        (cal.with_categories(&remaining), true)
+      }
+    }
+  };
+  Ok(result)
+}
+
+fn is_reschedule(command: &ModifyCommand) -> bool {
+  match command {
+    ModifyCommand::Shift { .. } | ModifyCommand::MoveTo { .. } => true,
+    _ => false,
+  }
+}
+
+/// Shifts DTSTART and DTEND of the principal event, keeping their timezones.
+fn shift_event(cal: IcalVCalendar, seconds: i64) -> KhResult<IcalVCalendar> {
+  let event = cal.get_principal_event();
+  let start = event.get_dtstart().ok_or("event has no start")?;
+  if start.is_date() && seconds % (24 * 60 * 60) != 0 {
+    Err("all-day events can only be shifted by whole days")?
+  }
+  let offset = IcalDuration::from_seconds(i32::try_from(seconds).map_err(|_| "offset is too large")?);
+
+  let end = if event.has_property_dtend() { event.get_dtend() } else { None };
+  let mut cal = cal.with_dtstart(&(start + offset.clone()));
+  if let Some(end) = end {
+    cal = cal.with_dtend(&(end + offset));
+  }
+  Ok(cal)
+}
+
+/// Moves the principal event to `new_start`, which is read as local time and
+/// stored in the timezone of the old DTSTART. Floating events stay floating at
+/// the same wall clock time. DTEND follows at the original duration, in the
+/// same timezone as the new start.
+fn move_event(cal: IcalVCalendar, new_start: &IcalTime) -> KhResult<IcalVCalendar> {
+  let event = cal.get_principal_event();
+  let start = event.get_dtstart().ok_or("event has no start")?;
+  if start.is_date() != new_start.is_date() {
+    Err("all-day events can only be moved to a date, other events only to a date-time")?
   }
+  let new_start = if start.is_date() {
+    new_start.clone()
+  } else if let Some(timezone) = start.get_timezone() {
+    new_start.with_timezone(&timezone)
+  } else {
+    // floating events stay floating
+    IcalTime::floating_ymd(new_start.year, new_start.month, new_start.day)
+      .and_hms(new_start.hour, new_start.minute, new_start.second)
+  };
 
+  let end = if event.has_property_dtend() { event.get_dtend() } else { None };
+  let new_end = match end {
+    Some(end) => {
+      let duration = end.timestamp() - start.timestamp();
+      Some(match new_start.get_timezone() {
+        Some(timezone) if !new_start.is_date() => {
+          IcalTime::from_timestamp(new_start.timestamp() + duration).with_timezone(&timezone)
+        }
+        // dates and floating times keep their wall clock duration
+        _ => new_start.clone() + IcalDuration::from_seconds(i32::try_from(duration).map_err(|_| "event is too long to move")?),
+      })
+    }
+    None => None,
+  };
+
+  let mut cal = cal.with_dtstart(&new_start);
+  if let Some(new_end) = new_end {
+    cal = cal.with_dtend(&new_end);
+  }
+  Ok(cal)
+}
+
+fn parse_new_start(start: &str) -> KhResult<IcalTime> {
+  if let Ok(datetime) = dateutil::datetime_from_str(start) {
+    return Ok(datetime.into());
+  }
+  match dateutil::date_from_str(start) {
+    Ok(date) => Ok(IcalTime::floating_ymd(date.year(), date.month() as i32, date.day() as i32)),
+    Err(_) => Err(format!("could not parse start {}", start))?,
+  }
+}
+
+fn write_modified(operation: &mut Operation, khline: &KhLine, cal: &IcalVCalendar) -> KhResult<()> {
+  info!("Modifying {}", cal.get_path_as_string().unwrap());
+
+  operation.modified(&khline.path)?;
+  write_cal(cal)?;
   Ok(())
 }
 
@@ -59,6 +275,7 @@ mod integration {
 
   use crate::cli::CommandLine;
   use crate::cli::Command::Modify;
+  use crate::utils::{fileutil, stdioutils};
   use structopt::StructOpt;
 
   #[test]
@@ -137,4 +354,202 @@ mod integration {
 //      assert!(do_modify(&x).is_ok());
 //    }
 //  }
+
+  fn modify_with_args(args: &[&str]) {
+    let args = CommandLine::from_iter(args);
+    if let Modify(x) = args.cmd {
+      do_modify(&x).unwrap();
+    }
+  }
+
+  fn read_allday_cal() -> IcalVCalendar {
+    "rfc_multi_day_allday.ics".parse::<KhLine>().unwrap().to_cal().unwrap()
+  }
+
+  #[test]
+  fn test_do_modify_set() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    modify_with_args(&["khaleesi", "modify", "set", "summary", "Jazz, again"]);
+
+    let cal = read_allday_cal();
+    let content = cal.to_string();
+    assert_eq!("Jazz, again", cal.get_principal_khevent().get_summary().unwrap());
+    assert!(content.contains("SEQUENCE:1\r\n"));
+    assert!(content.contains("LAST-MODIFIED:20130101T010203Z\r\n"));
+  }
+
+  #[test]
+  fn test_do_modify_set_uid() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "modify", "set", "UID", "foo"]);
+    if let Modify(x) = args.cmd {
+      assert!(do_modify(&x).is_err());
+    }
+  }
+
+  #[test]
+  fn test_do_modify_unset() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    modify_with_args(&["khaleesi", "modify", "unset", "TRANSP"]);
+
+    assert!(!read_allday_cal().to_string().contains("TRANSP"));
+  }
+
+  #[test]
+  fn test_do_modify_unset_required() {
+    let testdir = prepare_testdir("testdir_with_seq_and_cursor");
+
+    for property in &["UID", "dtstart"] {
+      stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+      let args = CommandLine::from_iter(&["khaleesi", "modify", "unset", property]);
+      if let Modify(x) = args.cmd {
+        assert!(do_modify(&x).is_err());
+      }
+    }
+
+    let original = fileutil::read_file_to_string(&path_to("testdir_with_seq_and_cursor/cal/rfc_multi_day_allday.ics")).unwrap();
+    testdir.child(".khaleesi/cal/rfc_multi_day_allday.ics").assert(original);
+  }
+
+  #[test]
+  fn test_do_modify_categories() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+    modify_with_args(&["khaleesi", "modify", "add-category", "music"]);
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+    modify_with_args(&["khaleesi", "modify", "add-category", "festival"]);
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+    modify_with_args(&["khaleesi", "modify", "add-category", "MUSIC"]);
+
+    let cal = read_allday_cal();
+    assert_eq!(vec!("music", "festival"), cal.get_categories());
+    assert!(cal.to_string().contains("SEQUENCE:2\r\n"));
+
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+    modify_with_args(&["khaleesi", "modify", "remove-category", "Music"]);
+
+    assert_eq!(vec!("festival"), read_allday_cal().get_categories());
+  }
+
+  #[test]
+  fn test_do_modify_shift_allday() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    modify_with_args(&["khaleesi", "modify", "shift", "+1d"]);
+
+    let content = read_allday_cal().to_string();
+    assert!(content.contains("DTSTART;VALUE=DATE:20070629\r\n"));
+    assert!(content.contains("DTEND;VALUE=DATE:20070710\r\n"));
+    assert_eq!("1183075200 rfc_multi_day_allday.ics\n", stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_do_modify_shift_allday_partial_day() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "modify", "shift", "-30m"]);
+    if let Modify(x) = args.cmd {
+      assert!(do_modify(&x).is_err());
+    }
+  }
+
+  #[test]
+  fn test_do_modify_shift_keeps_tzid() {
+    let testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics");
+
+    modify_with_args(&["khaleesi", "modify", "shift", "-30m"]);
+
+    let content = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap().to_cal().unwrap().to_string();
+    assert!(content.contains("DTSTART;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20181213T230000\r\n"));
+    assert!(content.contains("DTEND;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20181217T190000\r\n"));
+    testdir.child(".khaleesi/cursor").assert("1544738400 twodaysacrossbuckets.ics");
+  }
+
+  #[test]
+  fn test_do_modify_partial_failure_is_journaled() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics\n1182988800 rfc_multi_day_allday.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "modify", "shift", "-30m"]);
+    if let Modify(x) = args.cmd {
+      assert!(do_modify(&x).is_err());
+    }
+
+    let operations = crate::journal::read_journal().unwrap();
+    assert_eq!(1, operations.len());
+    assert_eq!(PathBuf::from("twodaysacrossbuckets.ics"), operations[0].changes[0].path);
+  }
+
+  #[test]
+  fn test_do_modify_move_to() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics");
+
+    modify_with_args(&["khaleesi", "modify", "move-to", "2019-01-07T10:00"]);
+
+    let content = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap().to_cal().unwrap().to_string();
+    assert!(content.contains("DTSTART;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20190107T100000\r\n"));
+    assert!(content.contains("DTEND;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20190111T060000\r\n"));
+  }
+
+  #[test]
+  fn test_do_modify_move_to_across_dst() {
+    crate::testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics");
+
+    modify_with_args(&["khaleesi", "modify", "move-to", "2019-07-01T10:00"]);
+
+    let content = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap().to_cal().unwrap().to_string();
+    assert!(content.contains("DTSTART;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20190701T100000\r\n"));
+    assert!(content.contains("DTEND;TZID=/freeassociation.sourceforge.net/Europe/Berlin:20190705T060000\r\n"));
+  }
+
+  #[test]
+  fn test_do_modify_move_to_floating() {
+    crate::testdata::setup();
+    let testdir = prepare_testdir("testdir");
+    let floating = crate::testdata::TEST_DTSTART_ONLY_DATETIME.replace("DTSTART:20070628T132900", "DTSTART:20070628T132900\nDTEND:20070628T152900");
+    testdir.child(".khaleesi/cal/floating.ics").write_str(&floating).unwrap();
+    stdioutils::test_stdin_write("floating.ics");
+
+    modify_with_args(&["khaleesi", "modify", "move-to", "2019-01-07T10:00"]);
+
+    let content = "floating.ics".parse::<KhLine>().unwrap().to_cal().unwrap().to_string();
+    assert!(content.contains("DTSTART:20190107T100000\r\n"));
+    assert!(content.contains("DTEND:20190107T120000\r\n"));
+  }
+
+  #[test]
+  fn test_do_modify_move_to_allday() {
+    let _testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    modify_with_args(&["khaleesi", "modify", "move-to", "2007-07-02"]);
+
+    let content = read_allday_cal().to_string();
+    assert!(content.contains("DTSTART;VALUE=DATE:20070702\r\n"));
+    assert!(content.contains("DTEND;VALUE=DATE:20070713\r\n"));
+  }
+
+  #[test]
+  fn test_do_modify_set_dry_run() {
+    let testdir = prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1182988800 rfc_multi_day_allday.ics");
+
+    modify_with_args(&["khaleesi", "modify", "--dry-run", "set", "SUMMARY", "changed"]);
+
+    let original = fileutil::read_file_to_string(&path_to("testdir_with_seq_and_cursor/cal/rfc_multi_day_allday.ics")).unwrap();
+    testdir.child(".khaleesi/cal/rfc_multi_day_allday.ics").assert(original);
+  }
 }
@@ -0,0 +1,164 @@
+use std::fs::remove_file;
+use std::path::{Path, PathBuf};
+
+use crate::actions::index;
+use crate::calendars;
+use crate::cursorfile;
+use crate::defaults;
+use crate::input;
+use crate::journal::Operation;
+use crate::khline::KhLine;
+use crate::seqfile;
+use crate::utils::fileutil;
+use crate::KhResult;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct MoveArgs {
+  /// the calendar to move the event to
+  #[structopt(name = "calendar")]
+  pub calendar: String,
+}
+
+pub fn do_move(args: &MoveArgs) -> KhResult<()> {
+  info!("do_move");
+
+  if !calendars::calendar_list().contains(&args.calendar) {
+    Err(format!("calendar {} does not exist", args.calendar))?
+  }
+
+  let khline = input::default_input_khline()?;
+  let new_path = target_path(&khline, &args.calendar)?;
+
+  let mut operation = Operation::begin("move")?;
+  let result = move_file(&mut operation, &khline, &new_path);
+  operation.commit()?;
+
+  index::update_index_or_warn(&[khline.path.clone(), new_path]);
+
+  let new_khline = result?;
+  info!("Moved {} to {}", khline.get_normalized_path().display(), new_khline.get_normalized_path().display());
+  khprintln!("{}", new_khline);
+
+  Ok(())
+}
+
+fn move_file(operation: &mut Operation, khline: &KhLine, new_path: &Path) -> KhResult<KhLine> {
+  operation.deleted(&khline.path)?;
+  operation.created(new_path);
+
+  let cal = khline.to_cal()?.with_path(new_path);
+  fileutil::write_cal(&cal)?;
+
+  let new_khline = KhLine::new(new_path, khline.get_time().cloned());
+  rewrite_cursorfile(khline, &new_khline)?;
+  rewrite_seqfile(khline, &new_khline)?;
+
+  remove_file(&khline.path)?;
+  Ok(new_khline)
+}
+
+fn target_path(khline: &KhLine, calendar: &str) -> KhResult<PathBuf> {
+  let filename = khline.path.file_name().ok_or("event has no file name")?;
+  let new_path = defaults::get_caldir().join(calendar).join(filename);
+
+  if new_path == khline.path {
+    Err(format!("event is already in calendar {}", calendar))?
+  }
+  if new_path.exists() {
+    Err(format!("file {} already exists", new_path.display()))?
+  }
+  Ok(new_path)
+}
+
+fn rewrite_cursorfile(old: &KhLine, new: &KhLine) -> KhResult<()> {
+  if let Ok(cursor) = cursorfile::read_cursorfile() {
+    if cursor.path == old.path {
+      let moved_cursor = KhLine::new(&new.path, cursor.get_time().cloned());
+      cursorfile::write_cursorfile(&moved_cursor.to_string())?;
+    }
+  }
+  Ok(())
+}
+
+/// Points all lines of the sequence file that refer to `old` to `new`,
+/// keeping their timestamps. Other lines are left untouched.
+fn rewrite_seqfile(old: &KhLine, new: &KhLine) -> KhResult<()> {
+  let lines = match seqfile::read_seqfile() {
+    Ok(lines) => lines,
+    Err(_) => return Ok(()),
+  };
+
+  let mut changed = false;
+  let mut content = String::new();
+  for line in lines {
+    match line.parse::<KhLine>() {
+      Ok(ref khline) if khline.path == old.path => {
+        content.push_str(&KhLine::new(&new.path, khline.get_time().cloned()).to_string());
+        changed = true;
+      }
+      _ => content.push_str(&line),
+    }
+    content.push('\n');
+  }
+
+  if changed {
+    seqfile::write_to_seqfile(&content)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+
+  #[test]
+  fn test_move() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics");
+
+    do_move(&MoveArgs { calendar: "second".to_string() }).unwrap();
+
+    testdir.child(".khaleesi/cal/first/twodaysacrossbuckets.ics").assert(predicate::path::missing());
+    testdir.child(".khaleesi/cal/second/twodaysacrossbuckets.ics").assert(predicate::path::exists());
+    assert_eq!("second/twodaysacrossbuckets.ics\n", stdioutils::test_stdout_clear());
+
+    let khline = "second/twodaysacrossbuckets.ics".parse::<KhLine>().unwrap();
+    assert_eq!("20181129T142636Z-11617-1000-1-0@pool-4-4", khline.to_event().unwrap().get_uid());
+  }
+
+  #[test]
+  fn test_move_rewrites_cursor_and_seq() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    testdir.child(".khaleesi/seq").write_str("1544740200 first/twodaysacrossbuckets.ics\n1544740200 second/second_sub/twodaysacrossbuckets.ics\n").unwrap();
+    testdir.child(".khaleesi/cursor").write_str("1544740200 first/twodaysacrossbuckets.ics").unwrap();
+    stdioutils::test_stdin_write("1544740200 first/twodaysacrossbuckets.ics");
+
+    do_move(&MoveArgs { calendar: "second".to_string() }).unwrap();
+
+    testdir.child(".khaleesi/cursor").assert("1544740200 second/twodaysacrossbuckets.ics");
+    testdir.child(".khaleesi/seq").assert("1544740200 second/twodaysacrossbuckets.ics\n1544740200 second/second_sub/twodaysacrossbuckets.ics\n");
+  }
+
+  #[test]
+  fn test_move_unknown_calendar() {
+    let _testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics");
+
+    assert!(do_move(&MoveArgs { calendar: "third".to_string() }).is_err());
+  }
+
+  #[test]
+  fn test_move_target_exists() {
+    let testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("second/second_sub/twodaysacrossbuckets.ics");
+
+    assert!(do_move(&MoveArgs { calendar: "first".to_string() }).is_err());
+    testdir.child(".khaleesi/cal/second/second_sub/twodaysacrossbuckets.ics").assert(predicate::path::exists());
+  }
+}
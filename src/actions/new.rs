@@ -1,30 +1,74 @@
+use crate::actions::index;
 use crate::calendars;
+use crate::config::Config;
 use crate::cursorfile;
 use crate::defaults;
-use crate::icalwrap::{IcalTime, IcalVCalendar};
+use crate::icalwrap::{IcalDuration, IcalTime, IcalVCalendar};
+use crate::journal::Operation;
 use crate::khline::KhLine;
-use crate::utils::{fileutil, misc};
+use crate::utils::{dateutil, fileutil, misc};
+use chrono::Utc;
 use crate::KhResult;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+/// Length of timed events that are given neither an end nor a duration
+const DEFAULT_DURATION_SECONDS: i32 = 60 * 60;
+
 #[derive(Debug, StructOpt)]
 pub struct NewArgs {
-  /// the calendar
-  #[structopt(name = "calendar")]
-  pub calendar: String,
-  /// from
+  /// start, either a date (for an all-day event) or a date-time
   #[structopt(name = "from")]
   pub from: String,
-  /// to
-  #[structopt(name = "to")]
-  pub to: String,
   /// summary
   #[structopt(name = "summary")]
   pub summary: String,
+  /// end, either a date or a date-time (default: one day or one hour after the start)
+  #[structopt(long = "to", raw(conflicts_with = r#""duration""#))]
+  pub to: Option<String>,
+  /// duration instead of an end, e.g. 1h30m, 45m, 2d or an iCalendar duration
+  #[structopt(short = "d", long = "duration")]
+  pub duration: Option<String>,
+  /// the calendar (default: default_calendar from the [new] config section)
+  #[structopt(short = "c", long = "calendar")]
+  pub calendar: Option<String>,
   /// location
-  #[structopt(name = "location")]
-  pub location: String,
+  #[structopt(short = "l", long = "location")]
+  pub location: Option<String>,
+  /// description
+  #[structopt(long = "description")]
+  pub description: Option<String>,
+  /// category, can be given multiple times
+  #[structopt(long = "category", raw(number_of_values = "1"))]
+  pub categories: Vec<String>,
+  /// url
+  #[structopt(long = "url")]
+  pub url: Option<String>,
+  /// repeat the event
+  #[structopt(long = "repeat", raw(possible_values = "&RepeatFrequency::variants()", case_insensitive = "true"))]
+  pub repeat: Option<RepeatFrequency>,
+  /// number of occurrences
+  #[structopt(long = "count", raw(requires = r#""repeat""#, conflicts_with = r#""until""#))]
+  pub count: Option<u32>,
+  /// date of the last possible occurrence
+  #[structopt(long = "until", raw(requires = r#""repeat""#))]
+  pub until: Option<String>,
+  /// repeat only every n-th day, week, month or year
+  #[structopt(long = "interval", raw(requires = r#""repeat""#))]
+  pub interval: Option<u32>,
+  /// weekdays to repeat on, e.g. MO,WE,FR or 1MO,-1FR for monthly events
+  #[structopt(long = "byday", raw(requires = r#""repeat""#))]
+  pub byday: Option<String>,
+}
+
+arg_enum! {
+#[derive(Debug)]
+  pub enum RepeatFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+  }
 }
 
 struct EventProperties {
@@ -32,25 +76,128 @@ struct EventProperties {
   from: IcalTime,
   to: IcalTime,
   summary: String,
-  location: String,
+  location: Option<String>,
+  description: Option<String>,
+  categories: Vec<String>,
+  url: Option<String>,
+  rrule: Option<String>,
 }
 
 impl EventProperties {
-  fn parse_from_args(args: &NewArgs) -> KhResult<EventProperties> {
-    let calendar = EventProperties::parse_calendar(&args.calendar)?;
+  fn parse_from_args(args: &NewArgs, config: &Config) -> KhResult<EventProperties> {
+    let calendar = match &args.calendar {
+      Some(calendar) => calendar.as_str(),
+      None => config.new.default_calendar.as_ref().map(String::as_str).ok_or("no calendar given and no default calendar configured")?,
+    };
+    let calendar = EventProperties::parse_calendar(calendar)?;
     let from = EventProperties::parse_from(&args.from)?;
-    let to = EventProperties::parse_to(&args.to)?;
+    let to = match (&args.to, &args.duration) {
+      (Some(to), _) => EventProperties::parse_to(to)?,
+      (None, Some(duration)) => {
+        let duration = EventProperties::parse_duration(duration)?;
+        if from.is_date() && (duration.to_seconds() <= 0 || duration.to_seconds() % (24 * 60 * 60) != 0) {
+          Err("all-day events can only last whole days")?
+        }
+        from.clone() + duration
+      }
+      (None, None) => EventProperties::default_to(&from),
+    };
+    if from.is_date() != to.is_date() {
+      Err("start and end must both be dates or both be date-times")?
+    }
+    if to.timestamp() < from.timestamp() {
+      Err("end is before start")?
+    }
     let summary = EventProperties::parse_summary(&args.summary)?;
-    let location = EventProperties::parse_location(&args.location)?;
+    let location = match &args.location {
+      Some(location) => Some(EventProperties::parse_location(location)?),
+      None => None,
+    };
+    let rrule = EventProperties::parse_rrule(args, &from)?;
     Ok(EventProperties {
       calendar,
       from,
       to,
       summary,
       location,
+      description: args.description.clone(),
+      categories: args.categories.clone(),
+      url: args.url.clone(),
+      rrule,
     })
   }
 
+  fn parse_rrule(args: &NewArgs, from: &IcalTime) -> KhResult<Option<String>> {
+    let frequency = match &args.repeat {
+      Some(frequency) => frequency,
+      None => return Ok(None),
+    };
+    let mut rrule = match frequency {
+      RepeatFrequency::Daily => "FREQ=DAILY",
+      RepeatFrequency::Weekly => "FREQ=WEEKLY",
+      RepeatFrequency::Monthly => "FREQ=MONTHLY",
+      RepeatFrequency::Yearly => "FREQ=YEARLY",
+    }.to_string();
+    if let Some(until) = &args.until {
+      rrule.push_str(&format!(";UNTIL={}", EventProperties::parse_until(until, from)?));
+    }
+    if let Some(count) = args.count {
+      if count == 0 {
+        Err("count must be at least 1")?
+      }
+      rrule.push_str(&format!(";COUNT={}", count));
+    }
+    if let Some(interval) = args.interval {
+      if interval == 0 {
+        Err("interval must be at least 1")?
+      }
+      rrule.push_str(&format!(";INTERVAL={}", interval));
+    }
+    if let Some(byday) = &args.byday {
+      rrule.push_str(&format!(";BYDAY={}", EventProperties::parse_byday(byday, frequency)?));
+    }
+    Ok(Some(rrule))
+  }
+
+  /// UNTIL has to be a date for all-day events, and a UTC date-time otherwise.
+  fn parse_until(arg: &str, from: &IcalTime) -> KhResult<String> {
+    let date = dateutil::date_from_str(arg).map_err(|_| format!("could not parse until date {}", arg))?;
+    if from.is_date() {
+      Ok(date.format("%Y%m%d").to_string())
+    } else {
+      let end_of_day = date.and_hms(23, 59, 59).with_timezone(&Utc);
+      Ok(end_of_day.format("%Y%m%dT%H%M%SZ").to_string())
+    }
+  }
+
+  /// Weekdays may carry an ordinal like `1MO` or `-1FR`, which only makes
+  /// sense for monthly and yearly repeats.
+  fn parse_byday(arg: &str, frequency: &RepeatFrequency) -> KhResult<String> {
+    let weekdays = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+    let days: Vec<String> = arg.split(',').map(|day| day.trim().to_uppercase()).collect();
+    for day in &days {
+      let (ordinal, weekday) = day.split_at(day.find(|c: char| c.is_ascii_alphabetic()).unwrap_or_else(|| day.len()));
+      if !weekdays.contains(&weekday) {
+        Err(format!("invalid weekday {} in byday", day))?
+      }
+      if ordinal.is_empty() {
+        continue;
+      }
+      match frequency {
+        RepeatFrequency::Monthly | RepeatFrequency::Yearly => (),
+        _ => Err(format!("numbered weekday {} in byday needs a monthly or yearly repeat", day))?,
+      }
+      let number = ordinal.trim_start_matches(|c: char| c == '+' || c == '-');
+      let valid = ordinal.len() - number.len() <= 1
+        && !number.starts_with('0')
+        && number.parse::<u32>().map(|n| n <= 53).unwrap_or(false);
+      if !valid {
+        Err(format!("invalid weekday number in {} in byday, must be between 1 and 53", day))?
+      }
+    }
+    Ok(days.join(","))
+  }
+
   fn parse_from(arg: &str) -> KhResult<IcalTime> {
     if arg.is_empty() {
       Err("no start date/time given")?
@@ -67,6 +214,27 @@ impl EventProperties {
     Ok(time)
   }
 
+  fn default_to(from: &IcalTime) -> IcalTime {
+    if from.is_date() {
+      from.succ()
+    } else {
+      from.clone() + IcalDuration::from_seconds(DEFAULT_DURATION_SECONDS)
+    }
+  }
+
+  /// Parses durations like `1h30m`, `45m` or `2d`, as well as iCalendar
+  /// durations like `PT1H30M`.
+  fn parse_duration(arg: &str) -> KhResult<IcalDuration> {
+    if arg.starts_with('P') || arg.starts_with("-P") {
+      return Ok(arg.parse::<IcalDuration>()?);
+    }
+    let seconds = dateutil::seconds_from_duration_str(arg)?;
+    if seconds < 0 {
+      Err(format!("invalid duration {}", arg))?
+    }
+    Ok(IcalDuration::from_seconds(seconds as i32))
+  }
+
   fn parse_location(arg: &str) -> KhResult<String> {
     if arg.is_empty() {
       Err("no location given")?
@@ -93,9 +261,9 @@ impl EventProperties {
   }
 }
 
-pub fn do_new(args: &NewArgs) -> KhResult<()> {
+pub fn do_new(args: &NewArgs, config: &Config) -> KhResult<()> {
   let uid = misc::make_new_uid();
-  let ep = EventProperties::parse_from_args(args)?;
+  let ep = EventProperties::parse_from_args(args, config)?;
 
   let path = assemble_file_path(&ep.calendar, &uid);
 
@@ -103,11 +271,21 @@ pub fn do_new(args: &NewArgs) -> KhResult<()> {
     .with_uid(&uid)?
     .with_dtstamp_now()
     .with_last_modified_now()
-    .with_eventprops(&ep);
+    .with_eventprops(&ep)?;
+
+  if let Some(errors) = new_cal.check_for_errors() {
+    Err(format!("new event contains errors:\n{}", errors.join("\n")))?
+  }
 
   let khline = KhLine::from(&new_cal);
 
-  fileutil::write_cal(&new_cal)?;
+  let mut operation = Operation::begin("new")?;
+  operation.created(&path);
+  let result = fileutil::write_cal(&new_cal);
+  operation.commit()?;
+  result?;
+
+  index::update_index_or_warn(&[path]);
 
   cursorfile::write_cursorfile(&khline.to_string())?;
   khprintln!("{}", khline);
@@ -124,12 +302,28 @@ fn assemble_file_path(cal_name: &String, uid: &String ) -> PathBuf {
 }
 
 impl IcalVCalendar {
-  fn with_eventprops(self, ep: &EventProperties) -> Self {
-    self
+  fn with_eventprops(self, ep: &EventProperties) -> KhResult<Self> {
+    let mut cal = self
       .with_dtstart(&ep.from)
       .with_dtend(&ep.to)
-      .with_summary(&ep.summary)
-      .with_location(&ep.location)
+      .with_summary(&ep.summary);
+    cal = match &ep.location {
+      Some(location) => cal.with_location(location),
+      None => cal.with_remove_property("LOCATION").0,
+    };
+    if let Some(description) = &ep.description {
+      cal = cal.with_description(description);
+    }
+    for category in &ep.categories {
+      cal = cal.with_category(category);
+    }
+    if let Some(url) = &ep.url {
+      cal = cal.with_url(url);
+    }
+    if let Some(rrule) = &ep.rrule {
+      cal = cal.with_rrule(rrule)?;
+    }
+    Ok(cal)
   }
 }
 
@@ -171,8 +365,11 @@ mod integration {
   use predicates::prelude::*;
 
   use super::*;
+  use crate::cli::CommandLine;
+  use crate::cli::Command::New;
   use crate::testdata;
   use crate::testutils;
+  use structopt::StructOpt;
 
   #[test]
   fn test_parse_calendar() {
@@ -249,17 +446,127 @@ mod integration {
   #[test]
   fn test_parse_from_args() {
     let _testdir = testutils::prepare_testdir("testdir_two_cals");
-    let args = NewArgs {
-      calendar: "second".to_string(),
-      from: "2017-11-03T12:30:00".to_string(),
-      to: "2017-11-07T11:11:00".to_string(),
-      summary: "summary text".to_string(),
-      location: "location text".to_string(),
-    };
-    let ep = EventProperties::parse_from_args(&args).unwrap();
-    assert_eq!("second".to_string(), ep.calendar);
-    assert_eq!("summary text".to_string(), ep.summary);
-    assert_eq!("location text".to_string(), ep.location);
+    let args = CommandLine::from_iter(&[
+      "khaleesi", "new", "2017-11-03T12:30:00", "summary text",
+      "--calendar", "second", "--to", "2017-11-07T11:11:00", "--location", "location text",
+    ]);
+    if let New(x) = args.cmd {
+      let ep = EventProperties::parse_from_args(&x, &Config::default()).unwrap();
+      assert_eq!("second".to_string(), ep.calendar);
+      assert_eq!("summary text".to_string(), ep.summary);
+      assert_eq!(Some("location text".to_string()), ep.location);
+    }
+  }
+
+  #[test]
+  fn test_parse_from_args_default_calendar() {
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03T12:30:00", "summary text"]);
+    if let New(x) = args.cmd {
+      assert!(EventProperties::parse_from_args(&x, &Config::default()).is_err());
+
+      let mut config = Config::default();
+      config.new.default_calendar = Some("first".to_string());
+      let ep = EventProperties::parse_from_args(&x, &config).unwrap();
+      assert_eq!("first".to_string(), ep.calendar);
+      assert_eq!(None, ep.location);
+    }
+  }
+
+  #[test]
+  fn test_parse_from_args_duration() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03T12:30:00", "summary text", "-c", "first", "--duration", "1h30m"]);
+    if let New(x) = args.cmd {
+      let ep = EventProperties::parse_from_args(&x, &Config::default()).unwrap();
+      let expected = IcalTime::floating_ymd(2017, 11, 3).and_hms(14, 0, 0);
+      assert_eq!(expected, ep.to);
+    }
+  }
+
+  #[test]
+  fn test_parse_from_args_allday() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03", "summary text", "-c", "first"]);
+    if let New(x) = args.cmd {
+      let ep = EventProperties::parse_from_args(&x, &Config::default()).unwrap();
+      assert!(ep.from.is_date());
+      assert_eq!(IcalTime::floating_ymd(2017, 11, 4), ep.to);
+    }
+  }
+
+  #[test]
+  fn test_parse_from_args_allday_duration() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03", "summary text", "-c", "first", "--duration", "2d"]);
+    if let New(x) = args.cmd {
+      let ep = EventProperties::parse_from_args(&x, &Config::default()).unwrap();
+      assert_eq!(IcalTime::floating_ymd(2017, 11, 5), ep.to);
+    }
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03", "summary text", "-c", "first", "--duration", "1h"]);
+    if let New(x) = args.cmd {
+      assert!(EventProperties::parse_from_args(&x, &Config::default()).is_err());
+    }
+  }
+
+  #[test]
+  fn test_parse_from_args_mixed_date_types() {
+    testdata::setup();
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03", "summary text", "-c", "first", "--to", "2017-11-04T12:00:00"]);
+    if let New(x) = args.cmd {
+      assert!(EventProperties::parse_from_args(&x, &Config::default()).is_err());
+    }
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03T12:00:00", "summary text", "-c", "first", "--to", "2017-11-04"]);
+    if let New(x) = args.cmd {
+      assert!(EventProperties::parse_from_args(&x, &Config::default()).is_err());
+    }
+  }
+
+  #[test]
+  fn test_new_args_conflicts() {
+    let base = ["khaleesi", "new", "2017-11-03T12:30:00", "summary text"];
+
+    let to_and_duration = [&base[..], &["--to", "2017-11-03T14:00:00", "--duration", "1h"]].concat();
+    assert!(CommandLine::from_iter_safe(&to_and_duration).is_err());
+    let count_without_repeat = [&base[..], &["--count", "3"]].concat();
+    assert!(CommandLine::from_iter_safe(&count_without_repeat).is_err());
+    let count_and_until = [&base[..], &["--repeat", "daily", "--count", "3", "--until", "2017-12-01"]].concat();
+    assert!(CommandLine::from_iter_safe(&count_and_until).is_err());
+    let repeat = [&base[..], &["--repeat", "daily", "--count", "3"]].concat();
+    assert!(CommandLine::from_iter_safe(&repeat).is_ok());
+  }
+
+  #[test]
+  fn test_parse_from_args_end_before_start() {
+    let _testdir = testutils::prepare_testdir("testdir_two_cals");
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03T12:30:00", "summary text", "-c", "first", "--to", "2017-11-02T12:30:00"]);
+    if let New(x) = args.cmd {
+      assert!(EventProperties::parse_from_args(&x, &Config::default()).is_err());
+    }
+  }
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(5400, EventProperties::parse_duration("1h30m").unwrap().to_seconds());
+    assert_eq!(2700, EventProperties::parse_duration("45m").unwrap().to_seconds());
+    assert_eq!(172800, EventProperties::parse_duration("2d").unwrap().to_seconds());
+    assert_eq!(5400, EventProperties::parse_duration("PT1H30M").unwrap().to_seconds());
+  }
+
+  #[test]
+  fn test_parse_duration_neg() {
+    assert!(EventProperties::parse_duration("").is_err());
+    assert!(EventProperties::parse_duration("90").is_err());
+    assert!(EventProperties::parse_duration("1x").is_err());
+    assert!(EventProperties::parse_duration("h").is_err());
   }
 
   //#[test]
@@ -283,19 +590,25 @@ mod integration {
       from: from.clone(),
       to: to.clone(),
       summary: summary.to_string(),
-      location: location.to_string(),
+      location: Some(location.to_string()),
+      description: Some("description".to_string()),
+      categories: vec!("work".to_string()),
+      url: None,
+      rrule: None,
     };
 
     let _testdir = testutils::prepare_testdir("testdir");
     let khline = "twodaysacrossbuckets.ics".parse::<KhLine>().unwrap();
 
-    let cal = khline.to_cal().unwrap().with_eventprops(&ep);
+    let cal = khline.to_cal().unwrap().with_eventprops(&ep).unwrap();
 
     let event = cal.get_principal_khevent();
     assert_eq!(Some(from), event.get_start());
     assert_eq!(Some(to), event.get_end());
     assert_eq!(summary, event.get_summary().unwrap());
     assert_eq!(location, event.get_location().unwrap());
+    assert_eq!("description", event.get_description().unwrap());
+    assert_eq!("work", event.get_properties_by_name("CATEGORIES")[0].get_value());
   }
 
   #[test]
@@ -303,16 +616,13 @@ mod integration {
     testdata::setup();
     let testdir = testutils::prepare_testdir("testdir_two_cals");
 
-    let args = NewArgs {
-      calendar: "second".to_string(),
-      from: "2017-11-03T12:30:00".to_string(),
-      to: "2017-11-07T11:11:00".to_string(),
-      summary: "summary text".to_string(),
-      location: "location text".to_string(),
-    };
-
-    let result = do_new(&args);
-    assert!(result.is_ok());
+    let args = CommandLine::from_iter(&[
+      "khaleesi", "new", "2017-11-03T12:30:00", "summary text",
+      "--calendar", "second", "--to", "2017-11-07T11:11:00", "--location", "location text",
+    ]);
+    if let New(x) = args.cmd {
+      do_new(&x, &Config::default()).unwrap();
+    }
 
     let expected = indoc!(
       "
@@ -355,4 +665,114 @@ mod integration {
     let cursor_expected = "1509708600 second/11111111-2222-3333-4444-444444444444@khaleesi.ics";
     testdir.child(".khaleesi/cursor").assert(cursor_expected);
   }
+
+  #[test]
+  fn test_do_new_updates_index() {
+    testdata::setup();
+    let testdir = testutils::prepare_testdir("testdir_two_cals");
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    let args = CommandLine::from_iter(&[
+      "khaleesi", "new", "2017-11-03T12:30:00", "summary text",
+      "--calendar", "second", "--to", "2017-11-07T11:11:00", "--location", "location text",
+    ]);
+    if let New(x) = args.cmd {
+      do_new(&x, &Config::default()).unwrap();
+    }
+
+    let line = "1509708600 second/11111111-2222-3333-4444-444444444444@khaleesi.ics\n";
+    testdir.child(".khaleesi/index/2017-W44").assert(line);
+    testdir.child(".khaleesi/index/2017-W45").assert(line);
+  }
+
+  #[test]
+  fn test_do_new_allday_without_location() {
+    testdata::setup();
+    let testdir = testutils::prepare_testdir("testdir_two_cals");
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03", "summary text", "-c", "second", "--url", "https://example.com"]);
+    if let New(x) = args.cmd {
+      do_new(&x, &Config::default()).unwrap();
+    }
+
+    let content = fileutil::read_file_to_string(
+      &testdir.path().join(".khaleesi/cal/second/11111111-2222-3333-4444-444444444444@khaleesi.ics")
+    ).unwrap();
+    assert!(content.contains("DTSTART;VALUE=DATE:20171103\r\n"));
+    assert!(content.contains("DTEND;VALUE=DATE:20171104\r\n"));
+    assert!(content.contains("URL:https://example.com\r\n"));
+    assert!(!content.contains("LOCATION"));
+  }
+
+  #[test]
+  fn test_parse_rrule() {
+    testdata::setup();
+    let from = IcalTime::floating_ymd(2017, 11, 3).and_hms(12, 30, 0);
+    let args = CommandLine::from_iter(&[
+      "khaleesi", "new", "2017-11-03T12:30:00", "summary text",
+      "--repeat", "weekly", "--count", "3", "--interval", "2", "--byday", "mo, we",
+    ]);
+    if let New(x) = args.cmd {
+      let rrule = EventProperties::parse_rrule(&x, &from).unwrap();
+      assert_eq!(Some("FREQ=WEEKLY;COUNT=3;INTERVAL=2;BYDAY=MO,WE".to_string()), rrule);
+    }
+
+    let args = CommandLine::from_iter(&["khaleesi", "new", "2017-11-03T12:30:00", "summary text"]);
+    if let New(x) = args.cmd {
+      assert_eq!(None, EventProperties::parse_rrule(&x, &from).unwrap());
+    }
+  }
+
+  #[test]
+  fn test_parse_until() {
+    testdata::setup();
+    let from = IcalTime::floating_ymd(2017, 11, 3);
+    assert_eq!("20171231", EventProperties::parse_until("2017-12-31", &from).unwrap());
+    let from = from.and_hms(12, 30, 0);
+    assert_eq!("20171231T225959Z", EventProperties::parse_until("2017-12-31", &from).unwrap());
+    assert!(EventProperties::parse_until("quatsch", &from).is_err());
+  }
+
+  #[test]
+  fn test_parse_byday() {
+    assert_eq!("MO,WE", EventProperties::parse_byday("mo, we", &RepeatFrequency::Weekly).unwrap());
+    assert_eq!("1MO,-1FR,+53SU", EventProperties::parse_byday("1mo,-1fr,+53su", &RepeatFrequency::Monthly).unwrap());
+    assert_eq!("20TU", EventProperties::parse_byday("20tu", &RepeatFrequency::Yearly).unwrap());
+    assert!(EventProperties::parse_byday("MO,XY", &RepeatFrequency::Weekly).is_err());
+    assert!(EventProperties::parse_byday("", &RepeatFrequency::Weekly).is_err());
+  }
+
+  #[test]
+  fn test_parse_byday_invalid_number() {
+    for byday in &["++MO", "1-MO", "0MO", "99MO", "54MO", "-0FR", "001MO", "+MO"] {
+      assert!(EventProperties::parse_byday(byday, &RepeatFrequency::Monthly).is_err(), "{}", byday);
+    }
+  }
+
+  #[test]
+  fn test_parse_byday_number_needs_monthly_or_yearly() {
+    assert!(EventProperties::parse_byday("1MO", &RepeatFrequency::Weekly).is_err());
+    assert!(EventProperties::parse_byday("-1FR", &RepeatFrequency::Daily).is_err());
+  }
+
+  #[test]
+  fn test_do_new_repeat() {
+    testdata::setup();
+    let testdir = testutils::prepare_testdir("testdir_two_cals");
+
+    let args = CommandLine::from_iter(&[
+      "khaleesi", "new", "2017-11-03T12:30:00", "team meeting",
+      "-c", "second", "--repeat", "weekly", "--count", "4", "--byday", "FR",
+    ]);
+    if let New(x) = args.cmd {
+      do_new(&x, &Config::default()).unwrap();
+    }
+
+    let path = testdir.path().join(".khaleesi/cal/second/11111111-2222-3333-4444-444444444444@khaleesi.ics");
+    let content = fileutil::read_file_to_string(&path).unwrap();
+    assert!(content.contains("RRULE:FREQ=WEEKLY;COUNT=4;BYDAY=FR\r\n"));
+
+    let event = KhLine::new(&path, None).to_event().unwrap();
+    assert_eq!(4, event.get_recur_datetimes().len());
+  }
 }
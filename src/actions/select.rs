@@ -1,9 +1,13 @@
-use std::path::PathBuf;
+use std::path::{Path,PathBuf};
 
 use crate::defaults;
+use crate::icalwrap::IcalTime;
 use crate::selectors::{SelectFilters,daterange::SelectFilterFrom,daterange::SelectFilterTo};
 use crate::utils::fileutil as utils;
+use crate::utils::misc;
+use crate::khevent::KhEvent;
 use crate::khline::KhLine;
+use crate::output::{self, EventRecord, OutputArgs, OutputFormat};
 use crate::KhResult;
 use structopt::StructOpt;
 
@@ -12,6 +16,8 @@ pub struct SelectArgs {
   /// the arguments for the selection
   #[structopt(name = "args")]
   pub args: Vec<String>,
+  #[structopt(flatten)]
+  pub output: OutputArgs,
 }
 
 impl SelectFilters {
@@ -48,12 +54,43 @@ impl SelectFilterTo {
   }
 }
 
-pub fn select_by_args(args: &[&str]) -> KhResult<()> {
+pub fn select_by_args(args: &[&str], format: OutputFormat) -> KhResult<()> {
+  let lines = select_lines(args)?;
+
+  if format == OutputFormat::Text {
+    for line in lines {
+      khprintln!("{}", line);
+    }
+  } else {
+    let records: Vec<EventRecord> = lines
+      .iter()
+      .filter_map(|line| match load_event(line) {
+        Ok(event) => Some(EventRecord::from(&event)),
+        Err(cause) => {
+          warn!("{}", cause);
+          None
+        }
+      })
+      .collect();
+    output::print_records(format, &records)?;
+  }
+
+  Ok(())
+}
+
+fn load_event(line: &str) -> KhResult<KhEvent> {
+  let khline = line.parse::<KhLine>()?;
+  Ok(khline.to_event()?)
+}
+
+/// The sorted lines of all events in the index that match the selection.
+pub fn select_lines(args: &[&str]) -> KhResult<Vec<String>> {
   let filters = SelectFilters::parse_from_args(args)?;
 
   let indexdir = defaults::get_indexdir();
 
   let mut buckets: Vec<PathBuf> = utils::file_iter(&indexdir)
+    .filter(|path| path.file_name().map_or(true, |name| name != misc::RECUR_BUCKET))
     .collect();
   buckets.sort_unstable();
   let buckets = buckets.into_iter()
@@ -74,13 +111,82 @@ pub fn select_by_args(args: &[&str]) -> KhResult<()> {
     .map(|event| KhLine::from(&event))
     .map(|khline| khline.to_string())
     .collect();
+  lines.append(&mut select_recurring(&filters, &indexdir));
 
   lines.sort_unstable();
   lines.dedup();
 
-  for line in lines {
-    println!("{}", line);
+  Ok(lines)
+}
+
+/// Unrolls all recurring events in the selected date range, regardless of how
+/// far ahead they have been unrolled in the index. Only applies to selections
+/// that are bounded by a "to" date.
+fn select_recurring(filters: &SelectFilters, indexdir: &Path) -> Vec<String> {
+  let until: IcalTime = match filters.to.date {
+    Some(date) => date.succ().into(),
+    None => return Vec::new(),
+  };
+  let from: Option<IcalTime> = filters.from.date.map(|date| date.into());
+
+  let recur_lines = match utils::read_lines_from_file(&indexdir.join(misc::RECUR_BUCKET)) {
+    Ok(lines) => lines,
+    Err(_) => return Vec::new(),
+  };
+
+  recur_lines
+    .map(|line| line.parse::<KhLine>())
+    .filter_map(|khline| khline.ok())
+    .map(|khline| khline.to_event())
+    .flatten()
+    .map(|master| {
+      let from = from.clone().or_else(|| master.get_start());
+      match from {
+        Some(from) => master
+          .get_recur_instances_between(&from, &until)
+          .filter(|event| filters.is_selected(event))
+          .map(|event| KhLine::from(&event).to_string())
+          .collect::<Vec<String>>(),
+        None => Vec::new(),
+      }
+    })
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::actions::index;
+  use crate::testdata;
+  use crate::testutils::prepare_testdir;
+  use crate::utils::stdioutils;
+
+  #[test]
+  fn test_select_recurring_beyond_horizon() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur");
+    defaults::set_recur_horizon(0, None);
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    select_by_args(&["from", "2030-01-07", "to", "2030-01-13"], OutputFormat::Text).unwrap();
+
+    assert_eq!("1894230000 weekly.ics\n", stdioutils::test_stdout_clear());
   }
 
-  Ok(())
+  #[test]
+  fn test_select_json() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir_with_recur");
+    defaults::set_recur_horizon(0, None);
+    index::action_index(&index::IndexArgs { reindex: false, path: None }).unwrap();
+
+    select_by_args(&["from", "2030-01-07", "to", "2030-01-13"], OutputFormat::Json).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    assert!(stdout.starts_with("[\n"));
+    assert!(stdout.contains(r#""path": "weekly.ics""#));
+    assert!(stdout.contains(r#""timestamp": 1894230000"#));
+  }
 }
@@ -1,11 +1,29 @@
 use crate::input;
+use crate::output::{self, EventRecord, OutputArgs, OutputFormat};
 use crate::utils::fileutil;
 use crate::KhResult;
+use structopt::StructOpt;
 
-pub fn do_show() -> KhResult<()> {
+#[derive(Debug, StructOpt)]
+pub struct ShowArgs {
+  #[structopt(flatten)]
+  pub output: OutputArgs,
+}
+
+pub fn do_show(args: &ShowArgs) -> KhResult<()> {
   info!("do_show");
   let lines = input::default_input_khlines()?;
 
+  if args.output.format != OutputFormat::Text {
+    let mut records = Vec::new();
+    for line in lines {
+      let mut record = EventRecord::from(&line.to_event()?);
+      record.ical = Some(fileutil::read_file_to_string(line.get_path())?);
+      records.push(record);
+    }
+    return output::print_records(args.output.format, &records);
+  }
+
   for line in lines {
     let output = fileutil::read_file_to_string(line.get_path()).unwrap();
     khprintln!("{}", output);
@@ -18,17 +36,37 @@ pub fn do_show() -> KhResult<()> {
 mod integration {
   use super::*;
 
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Show;
   use crate::testutils::*;
   use crate::utils::stdioutils::*;
+  use structopt::StructOpt;
 
   #[test]
   fn test_() {
     let _testdir = prepare_testdir("testdir_with_seq");
 
-    do_show().unwrap();
+    let args = CommandLine::from_iter(&["khaleesi", "show"]);
+    if let Show(x) = args.cmd {
+      do_show(&x).unwrap();
+    }
 
     let stdout = test_stdout_clear();
     assert_eq!(784, stdout.len());
     assert_eq!(32, stdout.lines().count());
   }
+
+  #[test]
+  fn test_json() {
+    let _testdir = prepare_testdir("testdir_with_seq");
+
+    let args = CommandLine::from_iter(&["khaleesi", "show", "--format", "jsonl"]);
+    if let Show(x) = args.cmd {
+      do_show(&x).unwrap();
+    }
+
+    let stdout = test_stdout_clear();
+    assert_eq!(1, stdout.lines().count());
+    assert!(stdout.contains(r#""ical":"BEGIN:VCALENDAR"#));
+  }
 }
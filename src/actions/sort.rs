@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use crate::input;
+use crate::khevent::KhEvent;
+use crate::khline::KhLine;
+use crate::seqfile;
+use crate::KhResult;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct SortArgs {
+  /// Sort by this key
+  #[structopt(
+    name = "key",
+    default_value = "start",
+    raw(possible_values = "&SortKey::variants()", case_insensitive = "true")
+  )]
+  pub key: SortKey,
+  /// Name of the property to sort by, if the key is "property"
+  #[structopt(name = "property")]
+  pub property: Option<String>,
+  /// Reverse the sort order
+  #[structopt(short = "r", long = "reverse")]
+  pub reverse: bool,
+  /// Only keep the first of identical lines
+  #[structopt(short = "u", long = "unique")]
+  pub unique: bool,
+  /// Also write the sorted lines to the sequence file
+  #[structopt(short = "s", long = "seq")]
+  pub seq: bool,
+}
+
+arg_enum! {
+#[derive(Debug)]
+  pub enum SortKey {
+    Start,
+    End,
+    Summary,
+    Calendar,
+    Duration,
+    Property,
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+  Number(i64),
+  Text(String),
+}
+
+pub fn action_sort(args: &SortArgs) -> KhResult<()> {
+  if let SortKey::Property = args.key {
+    if args.property.is_none() {
+      Err("sort property needs the name of a property")?;
+    }
+  }
+
+  let mut keyed_lines: Vec<(Option<SortValue>, KhLine)> = input::default_input_khlines()?
+    .filter_map(|khline| match khline.to_event() {
+      Ok(event) => Some((sort_value(&event, args), khline)),
+      Err(cause) => { warn!("{}", cause); None },
+    })
+    .collect();
+
+  // events without a value for the key always go last
+  keyed_lines.sort_by(|(a, _), (b, _)| {
+    let ordering = a.is_none().cmp(&b.is_none());
+    if args.reverse {
+      ordering.then_with(|| b.cmp(a))
+    } else {
+      ordering.then_with(|| a.cmp(b))
+    }
+  });
+
+  let mut seen = HashSet::new();
+  let lines: Vec<String> = keyed_lines
+    .into_iter()
+    .map(|(_, khline)| khline.to_string())
+    .filter(|line| !args.unique || seen.insert(line.clone()))
+    .collect();
+
+  for line in &lines {
+    khprintln!("{}", line);
+  }
+
+  if args.seq {
+    let mut content = lines.join("\n");
+    content.push_str("\n");
+    seqfile::write_to_seqfile(&content)?;
+  }
+
+  Ok(())
+}
+
+fn sort_value(event: &KhEvent, args: &SortArgs) -> Option<SortValue> {
+  match args.key {
+    SortKey::Start => event.get_start().map(|time| SortValue::Number(time.timestamp())),
+    SortKey::End => event.get_end().map(|time| SortValue::Number(time.timestamp())),
+    SortKey::Summary => event.get_summary().map(text_value),
+    SortKey::Calendar => event.get_calendar_name().map(text_value),
+    SortKey::Duration => event.get_duration().map(|duration| SortValue::Number(i64::from(duration.to_seconds()))),
+    SortKey::Property => {
+      let name = args.property.as_ref()?;
+      event
+        .get_properties_by_name(&name.to_uppercase())
+        .first()
+        .map(|property| text_value(property.get_value()))
+    }
+  }
+}
+
+fn text_value(text: String) -> SortValue {
+  SortValue::Text(text.to_lowercase())
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use assert_fs::prelude::*;
+  use crate::cli::CommandLine;
+  use crate::cli::Command::Sort;
+  use crate::testutils;
+  use crate::utils::stdioutils;
+  use structopt::StructOpt;
+
+  #[test]
+  fn test_sort_start() {
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = CommandLine::from_iter(&["khaleesi", "sort", "start"]);
+    if let Sort(x) = args.cmd {
+      action_sort(&x).unwrap();
+    }
+
+    let expected = "1182988800 rfc_multi_day_allday.ics\n1544740200 twodaysacrossbuckets.ics\n";
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_sort_summary_reverse() {
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = CommandLine::from_iter(&["khaleesi", "sort", "summary", "--reverse"]);
+    if let Sort(x) = args.cmd {
+      action_sort(&x).unwrap();
+    }
+
+    let expected = "1544740200 twodaysacrossbuckets.ics\n1182988800 rfc_multi_day_allday.ics\n";
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_sort_property() {
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = CommandLine::from_iter(&["khaleesi", "sort", "property", "uid"]);
+    if let Sort(x) = args.cmd {
+      action_sort(&x).unwrap();
+    }
+
+    let expected = "1182988800 rfc_multi_day_allday.ics\n1544740200 twodaysacrossbuckets.ics\n";
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_sort_property_missing_name() {
+    let _testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+
+    let args = CommandLine::from_iter(&["khaleesi", "sort", "property"]);
+    if let Sort(x) = args.cmd {
+      assert!(action_sort(&x).is_err());
+    }
+  }
+
+  #[test]
+  fn test_sort_unique_to_seq() {
+    let testdir = testutils::prepare_testdir("testdir_with_seq_and_cursor");
+    stdioutils::test_stdin_write("1544740200 twodaysacrossbuckets.ics\n1182988800 rfc_multi_day_allday.ics\n1544740200 twodaysacrossbuckets.ics");
+
+    let args = CommandLine::from_iter(&["khaleesi", "sort", "--unique", "--seq"]);
+    if let Sort(x) = args.cmd {
+      action_sort(&x).unwrap();
+    }
+
+    let expected = "1182988800 rfc_multi_day_allday.ics\n1544740200 twodaysacrossbuckets.ics\n";
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+    testdir.child(".khaleesi/seq").assert(expected);
+  }
+}
@@ -1,12 +1,81 @@
+use crate::actions::index;
 use crate::defaults;
+use crate::journal;
 use crate::KhResult;
-use crate::utils::stdioutils;
+use crate::utils::{lock, stdioutils};
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use structopt::StructOpt;
 use walkdir::WalkDir;
 
-pub fn do_undo() -> KhResult<()> {
+#[derive(Debug, StructOpt)]
+pub struct UndoArgs {
+  /// show the history of operations instead of undoing one
+  #[structopt(short = "l", long = "list")]
+  pub list: bool,
+}
+
+pub fn do_undo(args: &UndoArgs) -> KhResult<()> {
+  if args.list {
+    return list_operations();
+  }
+
+  let _lock = lock::lock_file_exclusive(&defaults::get_journallockfile())?;
+  let mut operations = journal::read_journal()?;
+  if operations.is_empty() {
+    return undo_most_recent_backup();
+  }
+
+  let operation = operations
+    .iter_mut()
+    .rev()
+    .find(|operation| !operation.undone)
+    .ok_or("nothing to undo!")?;
+
+  info!("Undoing {} {}", operation.action, operation.id);
+  let paths = operation.undo()?;
+  khprintln!("Undid {} ({})", operation.action, operation.id);
+
+  journal::write_journal(&operations)?;
+  index::update_index_or_warn(&paths);
+
+  Ok(())
+}
+
+pub fn do_redo() -> KhResult<()> {
+  let _lock = lock::lock_file_exclusive(&defaults::get_journallockfile())?;
+  let mut operations = journal::read_journal()?;
+
+  let operation = operations
+    .iter_mut()
+    .find(|operation| operation.undone)
+    .ok_or("nothing to redo!")?;
+
+  info!("Redoing {} {}", operation.action, operation.id);
+  let paths = operation.redo()?;
+  khprintln!("Redid {} ({})", operation.action, operation.id);
+
+  journal::write_journal(&operations)?;
+  index::update_index_or_warn(&paths);
+
+  Ok(())
+}
+
+fn list_operations() -> KhResult<()> {
+  for operation in journal::read_journal()? {
+    let state = if operation.undone { " (undone)" } else { "" };
+    khprintln!("{} {}{}", operation.id, operation.action, state);
+    for change in &operation.changes {
+      khprintln!("  {} {}", change.kind, change.path.display());
+    }
+  }
+  Ok(())
+}
+
+/// Restores the files in the most recent backup directory that was taken
+/// before there was a journal. Backups of operations are left to the journal.
+fn undo_most_recent_backup() -> KhResult<()> {
   let backupdir = defaults::get_backupdir();
 
   let source_dir = get_most_recent_backup()?;
@@ -20,14 +89,19 @@ pub fn do_undo() -> KhResult<()> {
     .flatten()
     .filter(|dir_entry| dir_entry.path().is_file());
 
+  let mut restored = Vec::new();
   for file in files {
-    restore_file_from_backup(&source_dir, &file.path())?;
+    if let Some(target_path) = restore_file_from_backup(&source_dir, &file.path())? {
+      restored.push(target_path);
+    }
   };
 
+  index::update_index_or_warn(&restored);
+
   Ok(())
 }
 
-fn restore_file_from_backup(source_prefix: &Path, file_path: &Path) -> KhResult<()> {
+fn restore_file_from_backup(source_prefix: &Path, file_path: &Path) -> KhResult<Option<PathBuf>> {
   let caldir = defaults::get_caldir();
   let path_in_cal = file_path.strip_prefix(source_prefix)?;
 
@@ -36,14 +110,14 @@ fn restore_file_from_backup(source_prefix: &Path, file_path: &Path) -> KhResult<
 
   if target_path.exists() && !ask_overwrite(&target_path) {
     info!("ignoring {}", target_path.display());
-    return Ok(());
+    return Ok(None);
   }
   fs::create_dir_all(&target_path.parent().ok_or_else(|| "error creating calendar directory")?)?;
   fs::copy(file_path, &target_path)?;
 
   info!("Restore {} to {}", file_path.display(), target_path.display());
 
-  Ok(())
+  Ok(Some(target_path))
 }
 
 fn get_most_recent_backup() -> KhResult<PathBuf> {
@@ -51,6 +125,7 @@ fn get_most_recent_backup() -> KhResult<PathBuf> {
   backupdir
     .read_dir()?
     .filter_map(|result| result.ok())
+    .filter(|dir_entry| journal::parse_operation_id(&dir_entry.file_name().to_string_lossy()).is_none())
     .map(|dir_entry| dir_entry.path())
     .max()
     .ok_or_else(|| "there are no backups, nothing to undo!".into())
@@ -82,6 +157,14 @@ mod tests {
     assert_eq!("backup_id", result.file_name().unwrap().to_str().unwrap());
   }
 
+  #[test]
+  fn test_get_most_recent_backup_skips_operations() {
+    let testdir = prepare_testdir("testdir");
+    testdir.child(".khaleesi/backup/2013-01-01T02:02:03/before/twodaysacrossbuckets.ics").touch().unwrap();
+
+    assert!(get_most_recent_backup().is_err());
+  }
+
   #[test]
   #[should_panic]
   fn test_get_most_recent_backup_negative() {
@@ -121,11 +204,114 @@ mod integration {
   use assert_fs::prelude::*;
   use predicates::prelude::*;
 
+  use crate::actions::copy;
+  use crate::actions::delete;
+  use crate::cli::CommandLine;
+  use crate::cli::Command::{Delete, Undo};
+  use crate::testdata;
+  use crate::utils::{fileutil, stdioutils};
+  use structopt::StructOpt;
+
   #[test]
   fn test_do_undo() {
     let testdir = prepare_testdir("testdir_with_backup");
-    do_undo().unwrap();
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
     let target_folder = testdir.child(".khaleesi/cal/my_calendar/twodaysacrossbuckets.ics");
     target_folder.assert(predicate::path::exists());
   }
+
+  #[test]
+  fn test_undo_redo_copy() {
+    testdata::setup();
+    let testdir = prepare_testdir("testdir");
+    let copied = testdir.child(".khaleesi/cal/11111111-2222-3333-4444-444444444444@khaleesi.ics");
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+    copy::do_copy().unwrap();
+    copied.assert(predicate::path::exists());
+
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
+    copied.assert(predicate::path::missing());
+    assert_eq!("Undid copy (2013-01-01T02:02:03)\n", stdioutils::test_stdout_clear());
+
+    do_redo().unwrap();
+    copied.assert(predicate::path::exists());
+  }
+
+  #[test]
+  fn test_undo_multiple() {
+    let testdir = prepare_testdir("testdir");
+    let original = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+    let content = fileutil::read_file_to_string(original.path()).unwrap();
+    let copied = testdir.child(".khaleesi/cal/11111111-2222-3333-4444-444444444444@khaleesi.ics");
+
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+    copy::do_copy().unwrap();
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--yes"]);
+    if let Delete(x) = args.cmd {
+      delete::do_delete(&x).unwrap();
+    }
+    original.assert(predicate::path::missing());
+
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
+    original.assert(content.as_str());
+    copied.assert(predicate::path::exists());
+
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
+    copied.assert(predicate::path::missing());
+
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      assert!(do_undo(&x).is_err());
+    }
+  }
+
+  #[test]
+  fn test_redo_nothing() {
+    let _testdir = prepare_testdir("testdir");
+    assert!(do_redo().is_err());
+  }
+
+  #[test]
+  fn test_undo_list() {
+    testdata::setup();
+    let _testdir = prepare_testdir("testdir");
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+    copy::do_copy().unwrap();
+    stdioutils::test_stdin_write("twodaysacrossbuckets.ics");
+    let args = CommandLine::from_iter(&["khaleesi", "delete", "--yes"]);
+    if let Delete(x) = args.cmd {
+      delete::do_delete(&x).unwrap();
+    }
+    let args = CommandLine::from_iter(&["khaleesi", "undo"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
+    stdioutils::test_stdout_clear();
+
+    let args = CommandLine::from_iter(&["khaleesi", "undo", "--list"]);
+    if let Undo(x) = args.cmd {
+      do_undo(&x).unwrap();
+    }
+
+    let expected = indoc!("
+      2013-01-01T02:02:03 copy
+        created 11111111-2222-3333-4444-444444444444@khaleesi.ics
+      2013-01-01T02:02:03-1 delete (undone)
+        deleted twodaysacrossbuckets.ics
+    ");
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
 }
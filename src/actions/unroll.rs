@@ -4,34 +4,125 @@ use structopt::StructOpt;
 use crate::khline::KhLine;
 use crate::KhResult;
 use crate::khevent::KhEvent;
+use crate::output::{self, EventRecord, OutputArgs, OutputFormat};
 
 #[derive(Debug, StructOpt)]
 pub struct UnrollArgs {
   /// The file to unroll
   #[structopt(name = "path", parse(from_os_str))]
   pub path: PathBuf,
+  #[structopt(flatten)]
+  pub output: OutputArgs,
 }
 
 pub fn action_unroll(args: &UnrollArgs) -> KhResult<()> {
   let filepath = &args.path;
-  do_unroll(filepath)?;
+  if args.output.format == OutputFormat::Text {
+    do_unroll(filepath)?;
+  } else {
+    do_unroll_records(filepath, args.output.format)?;
+  }
 
   Ok(())
 }
 
 fn do_unroll(filepath: &Path) -> KhResult<()> {
+  let (path, instances) = unroll_instances(filepath)?;
+  for instance in instances {
+    if let Some(start) = instance.get_start() {
+      khprintln!("{} {}", start.timestamp(), path);
+    }
+  }
+  Ok(())
+}
+
+fn do_unroll_records(filepath: &Path, format: OutputFormat) -> KhResult<()> {
+  let (_, instances) = unroll_instances(filepath)?;
+  let records: Vec<EventRecord> = instances.iter().map(EventRecord::from).collect();
+  output::print_records(format, &records)
+}
+
+/// The occurrences of the recurring events in the file, with overrides in
+/// place of the occurrences they replace, ordered by start. Also returns the
+/// path of the file as given.
+fn unroll_instances(filepath: &Path) -> KhResult<(String, Vec<KhEvent>)> {
   let path = filepath.to_str().ok_or_else(|| "str to path failed")?;
   let khline = path.parse::<KhLine>()?;
   let cal = khline.to_cal()?;
 
+  let mut instances = Vec::new();
   for ical_event in cal.events_iter() {
     let event = KhEvent::from_event(ical_event);
     if event.is_recur_master() {
-      let recurs = event.get_recur_datetimes();
-      for datetime in recurs {
-        println!("{} {}", datetime.timestamp(), cal.get_path_as_string().unwrap_or_else(|| "".to_string()));
-      }
+      instances.extend(event.get_recur_instances());
+    } else if event.is_recur_override() {
+      instances.push(event);
     }
   }
-  Ok(())
+  instances.sort_by_key(|event| event.get_start().map(|start| start.timestamp()));
+
+  let path = cal.get_path_as_string().unwrap_or_else(|| "".to_string());
+  Ok((path, instances))
+}
+
+#[cfg(test)]
+mod integration {
+  use super::*;
+
+  use crate::testdata;
+  use crate::testutils::{path_to, prepare_testdir_empty};
+  use crate::utils::stdioutils;
+
+  #[test]
+  fn test_unroll_rdate() {
+    testdata::setup();
+    let _testdir = prepare_testdir_empty();
+    let path = path_to("khal/event_d_rdate.ics");
+
+    do_unroll(&path).unwrap();
+
+    // all-day occurrences start at local midnight, like in the index
+    let expected: String = [1439330400, 1439416800, 1439503200, 1439589600]
+      .iter()
+      .map(|timestamp| format!("{} {}\n", timestamp, path.display()))
+      .collect();
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_unroll_override() {
+    testdata::setup();
+    let testdir = prepare_testdir_empty();
+    let path = testdir.path().join("override.ics");
+    std::fs::write(&path, testdata::TEST_EVENT_RECUR_OVERRIDE).unwrap();
+
+    do_unroll(&path).unwrap();
+
+    // the occurrence on 2018-10-18 was moved to 2018-10-19, which as a
+    // floating date starts at midnight UTC
+    let expected: String = [1539208800, 1539907200, 1540418400]
+      .iter()
+      .map(|timestamp| format!("{} {}\n", timestamp, path.display()))
+      .collect();
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+
+  #[test]
+  fn test_unroll_override_jsonl() {
+    testdata::setup();
+    let testdir = prepare_testdir_empty();
+    let path = testdir.path().join("override.ics");
+    std::fs::write(&path, testdata::TEST_EVENT_RECUR_OVERRIDE).unwrap();
+
+    do_unroll_records(&path, OutputFormat::Jsonl).unwrap();
+
+    let stdout = stdioutils::test_stdout_clear();
+    let records: Vec<serde_json::Value> = stdout
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    let timestamps: Vec<i64> = records.iter().map(|record| record["timestamp"].as_i64().unwrap()).collect();
+    assert_eq!(vec![1539208800, 1539907200, 1540418400], timestamps);
+    assert_eq!("Autocrypt Friday", records[1]["summary"]);
+  }
 }
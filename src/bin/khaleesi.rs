@@ -1,5 +1,5 @@
 #![allow(clippy::redundant_closure)] // disable "redundant closure" lint
-use log::{debug, error};
+use log::{debug, error, warn};
 
 use khaleesi::actions::*;
 use khaleesi::cli;
@@ -31,6 +31,10 @@ fn main() {
   let config = Config::read_config();
 
   init_local_timezone(&config);
+  init_recur_horizon(&config);
+  init_backup_retention(&config);
+  init_date_format(&config);
+  init_display_timezone(&config);
 
   let result = main_internal(&args, &config);
   if let Err(error) = result {
@@ -43,26 +47,36 @@ fn main_internal(args: &cli::CommandLine, config: &Config) -> KhResult<()> {
     cli::Command::Agenda(x) => agenda::show_events(
       &config,
       &x.args.iter().map(|x| x.as_ref()).collect::<Vec<&str>>(),
+      x.output.format,
+      x.timezone.as_ref().map(String::as_str),
     ),
+    cli::Command::Backup(x) => backup::do_backup(x),
+    cli::Command::Calendar(x) => calendar::do_calendar(x, config),
     cli::Command::Copy => copy::do_copy(),
     cli::Command::Cursor(x) => cursor::do_cursor(x),
-    cli::Command::Delete => delete::do_delete(),
-    cli::Command::Edit => edit::do_edit(),
+    cli::Command::Delete(x) => delete::do_delete(x),
+    cli::Command::Edit(x) => edit::do_edit(x),
+    cli::Command::Export(x) => export::do_export(x),
     cli::Command::Get(x) => get::action_get(x),
     cli::Command::GenCompletions(x) => gen_completions::gen_completions(x),
+    cli::Command::Grep(x) => grep::action_grep(x),
+    cli::Command::Import(x) => import::do_import(x),
     cli::Command::Index(x) => index::action_index(x),
     cli::Command::List(x) => {
-      list::list_by_args(&x.args.iter().map(|x| x.as_ref()).collect::<Vec<&str>>())
+      list::list_by_args(&x.args.iter().map(|x| x.as_ref()).collect::<Vec<&str>>(), x.output.format)
     }
     cli::Command::Modify(x) => modify::do_modify(x),
-    cli::Command::New(x) => new::do_new(x),
+    cli::Command::Move(x) => mv::do_move(x),
+    cli::Command::New(x) => new::do_new(x, config),
+    cli::Command::Redo => undo::do_redo(),
     cli::Command::Select(x) => {
-      select::select_by_args(&x.args.iter().map(|x| x.as_ref()).collect::<Vec<&str>>())
+      select::select_by_args(&x.args.iter().map(|x| x.as_ref()).collect::<Vec<&str>>(), x.output.format)
     }
     cli::Command::Seq => seq::action_seq(),
     //      "pretty" => prettyprint::prettyprint(),
-    cli::Command::Show => show::do_show(),
-    cli::Command::Undo => undo::do_undo(),
+    cli::Command::Show(x) => show::do_show(x),
+    cli::Command::Sort(x) => sort::action_sort(x),
+    cli::Command::Undo(x) => undo::do_undo(x),
     cli::Command::Unroll(x) => unroll::action_unroll(&x),
   }
 }
@@ -85,3 +99,25 @@ fn init_local_timezone(config: &Config) {
     env::set_var("TZ", &local_tz_config.timezone);
   }
 }
+
+fn init_recur_horizon(config: &Config) {
+  use khaleesi::defaults;
+  defaults::set_recur_horizon(config.index.recur_horizon_days, config.index.recur_history_days);
+}
+
+fn init_backup_retention(config: &Config) {
+  use khaleesi::defaults;
+  defaults::set_backup_retention(config.backup.keep_days, config.backup.keep_operations);
+}
+
+fn init_date_format(config: &Config) {
+  use khaleesi::defaults;
+  defaults::set_date_format(config.format.get_date_format());
+}
+
+fn init_display_timezone(config: &Config) {
+  use khaleesi::defaults;
+  if let Err(error) = defaults::set_display_timezone(config.agenda.timezone.as_ref().map(String::as_str)) {
+    warn!("{}", error);
+  }
+}
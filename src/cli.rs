@@ -2,12 +2,23 @@ use structopt::StructOpt;
 
 use crate::actions::gen_completions::GenCompletionsArgs;
 use crate::actions::agenda::AgendaArgs;
+use crate::actions::backup::BackupArgs;
+use crate::actions::calendar::CalendarArgs;
 use crate::actions::cursor::CursorArgs;
+use crate::actions::delete::DeleteArgs;
+use crate::actions::edit::EditArgs;
+use crate::actions::export::ExportArgs;
 use crate::actions::get::GetArgs;
+use crate::actions::grep::GrepArgs;
+use crate::actions::import::ImportArgs;
 use crate::actions::index::IndexArgs;
 use crate::actions::list::ListArgs;
 use crate::actions::modify::ModifyArgs;
+use crate::actions::mv::MoveArgs;
 use crate::actions::select::SelectArgs;
+use crate::actions::show::ShowArgs;
+use crate::actions::sort::SortArgs;
+use crate::actions::undo::UndoArgs;
 use crate::actions::unroll::UnrollArgs;
 use crate::actions::new::NewArgs;
 
@@ -31,6 +42,12 @@ pub enum Command {
   /// Show agenda view
   #[structopt(name = "agenda", author = "")]
   Agenda(AgendaArgs),
+  /// Manage backups
+  #[structopt(name = "backup", author = "")]
+  Backup(BackupArgs),
+  /// Show a month or week view
+  #[structopt(name = "calendar", author = "")]
+  Calendar(CalendarArgs),
   /// Copy event
   #[structopt(name = "copy", author = "")]
   Copy,
@@ -39,18 +56,29 @@ pub enum Command {
   Cursor(CursorArgs),
   /// Delete event
   ///
-  /// deletes a single event, either from stdin or the current cursor
+  /// deletes the events from stdin, or the event at the current cursor
   #[structopt(name = "delete", author = "")]
-  Delete,
+  Delete(DeleteArgs),
   /// Edit event
   #[structopt(name = "edit", author = "")]
-  Edit,
+  Edit(EditArgs),
+  /// Export events as a single ics file
+  #[structopt(name = "export", author = "")]
+  Export(ExportArgs),
   /// Get info about the calendar data
   #[structopt(name = "get", author = "")]
   Get(GetArgs),
   /// Print shell completions script to stdout
   #[structopt(name = "gen-completions", author = "")]
   GenCompletions(GenCompletionsArgs),
+  /// Search events in the sequence
+  #[structopt(name = "grep", author = "")]
+  Grep(GrepArgs),
+  /// Import events from an ics file
+  ///
+  /// splits the file into one file per event, from stdin if no file is given
+  #[structopt(name = "import", author = "")]
+  Import(ImportArgs),
   /// Rebuild index
   #[structopt(name = "index", author = "")]
   Index(IndexArgs),
@@ -60,9 +88,15 @@ pub enum Command {
   /// Modify an event
   #[structopt(name = "modify", author = "")]
   Modify(ModifyArgs),
+  /// Move event to another calendar
+  #[structopt(name = "move", author = "")]
+  Move(MoveArgs),
   /// Create new event
   #[structopt(name = "new", author = "")]
   New(NewArgs),
+  /// Redo the most recently undone action
+  #[structopt(name = "redo", author = "")]
+  Redo,
   /// Select from the index
   #[structopt(name = "select", author = "")]
   Select(SelectArgs),
@@ -71,10 +105,15 @@ pub enum Command {
   Seq,
   /// Show the raw ical file of an event
   #[structopt(name = "show", author = "")]
-  Show,
+  Show(ShowArgs),
+  /// Sort the sequence
+  #[structopt(name = "sort", author = "")]
+  Sort(SortArgs),
   /// Undo the most recent action
+  ///
+  /// undoes one action at a time, use --list to show the history
   #[structopt(name = "undo", author = "")]
-  Undo,
+  Undo(UndoArgs),
   /// Unroll a recurring event
   #[structopt(name = "unroll", author = "")]
   Unroll(UnrollArgs),
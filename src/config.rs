@@ -3,13 +3,19 @@ use toml;
 use yansi::{self,Style,Color};
 
 use crate::defaults;
+use crate::utils::dateformat::{self,DateFormat,Locale};
 use crate::utils::fileutil as utils;
+use crate::utils::template;
 
 #[derive(Deserialize,Debug,PartialEq)]
 #[serde(default)]
 pub struct Config {
   pub calendars: HashMap<String,CalendarConfig>,
   pub agenda: AgendaConfig,
+  pub index: IndexConfig,
+  pub new: NewConfig,
+  pub backup: BackupConfig,
+  pub format: FormatConfig,
   pub local_tz: Option<LocalTZConfig>
 }
 
@@ -18,6 +24,49 @@ pub struct Config {
 pub struct AgendaConfig {
   pub print_week_separator: bool,
   pub print_empty_days: bool,
+  pub event_format: String,
+  pub allday_format: Option<String>,
+  pub continuation_format: Option<String>,
+  pub cursor_format: Option<String>,
+  pub timezone: Option<String>,
+}
+
+#[derive(Deserialize,Debug,PartialEq)]
+#[serde(default)]
+pub struct IndexConfig {
+  pub recur_horizon_days: u32,
+  pub recur_history_days: Option<u32>,
+}
+
+#[derive(Deserialize,Debug,PartialEq,Default)]
+#[serde(default)]
+pub struct NewConfig {
+  pub default_calendar: Option<String>,
+}
+
+#[derive(Deserialize,Debug,PartialEq,Default)]
+#[serde(default)]
+pub struct BackupConfig {
+  pub keep_days: Option<u32>,
+  pub keep_operations: Option<usize>,
+}
+
+#[derive(Deserialize,Debug,PartialEq)]
+#[serde(default)]
+pub struct FormatConfig {
+  pub locale: String,
+  pub date_format: String,
+  pub time_format: Option<String>,
+  pub header_format: String,
+  pub clock: Clock,
+}
+
+#[derive(Deserialize,Debug,PartialEq,Clone,Copy)]
+pub enum Clock {
+  #[serde(rename = "24h")]
+  TwentyFourHour,
+  #[serde(rename = "12h")]
+  TwelveHour,
 }
 
 #[derive(Deserialize,Debug,PartialEq)]
@@ -38,7 +87,12 @@ impl Config {
   pub fn read_config() -> Self {
     let config = utils::read_file_to_string(&defaults::get_configfile());
     match config {
-      Ok(config) => toml::from_str(&config).unwrap(),
+      Ok(config) => {
+        let mut config: Config = toml::from_str(&config).unwrap();
+        config.agenda.check_line_formats();
+        config.format.check_formats();
+        config
+      }
       Err(_) => Config::default()
     }
   }
@@ -60,11 +114,124 @@ impl LocalTZConfig {
   }
 }
 
+/// The fields an agenda line template can use, see `agenda::event_line`.
+const AGENDA_LINE_FIELDS: &[&str] = &[
+  "index", "cursor", "start", "end", "time", "summary", "calendar", "location", "uid", "tzid",
+];
+
+impl AgendaConfig {
+  /// Checks the line templates once, a template with errors is replaced by
+  /// the default so that it is not reported for every line.
+  fn check_line_formats(&mut self) {
+    if let Err(error) = template::check(&self.event_format, AGENDA_LINE_FIELDS) {
+      warn!("{} in agenda.event_format, using the default", error);
+      self.event_format = AgendaConfig::default().event_format;
+    }
+    let formats = vec![
+      ("allday_format", &mut self.allday_format),
+      ("continuation_format", &mut self.continuation_format),
+      ("cursor_format", &mut self.cursor_format),
+    ];
+    for (name, format) in formats {
+      let error = match format {
+        Some(format) => template::check(format, AGENDA_LINE_FIELDS).err(),
+        None => None,
+      };
+      if let Some(error) = error {
+        warn!("{} in agenda.{}, using agenda.event_format", error, name);
+        *format = None;
+      }
+    }
+  }
+
+  /// The template for an event line. The cursor line takes precedence, then
+  /// all-day events, then continuations of events that started on an earlier
+  /// day. Each falls back to `event_format` if it is not configured.
+  pub fn get_line_format(&self, is_cursor: bool, is_allday: bool, is_continuation: bool) -> &str {
+    let format = if is_cursor {
+      &self.cursor_format
+    } else if is_allday {
+      &self.allday_format
+    } else if is_continuation {
+      &self.continuation_format
+    } else {
+      &None
+    };
+    format.as_ref().unwrap_or(&self.event_format)
+  }
+}
+
+impl FormatConfig {
+  /// Checks the strftime patterns once, chrono panics when it formats an
+  /// invalid one. A pattern with errors is replaced by the default.
+  fn check_formats(&mut self) {
+    if let Err(error) = dateformat::check_date_pattern(&self.date_format) {
+      warn!("{} in format.date_format, using the default", error);
+      self.date_format = DateFormat::default().date_format;
+    }
+    let error = match &self.time_format {
+      Some(time_format) => dateformat::check_pattern(time_format).err(),
+      None => None,
+    };
+    if let Some(error) = error {
+      warn!("{} in format.time_format, using the format of the clock", error);
+      self.time_format = None;
+    }
+  }
+
+  /// The formats to print dates and times with. An unsupported locale falls
+  /// back to English, `time_format` defaults to the format of the `clock`.
+  pub fn get_date_format(&self) -> DateFormat {
+    let locale = self.locale.parse::<Locale>().unwrap_or_else(|error| {
+      warn!("{}, using English", error);
+      Locale::En
+    });
+    let time_format = match (&self.time_format, self.clock) {
+      (Some(time_format), _) => time_format.clone(),
+      (None, Clock::TwentyFourHour) => "%H:%M".to_string(),
+      (None, Clock::TwelveHour) => "%I:%M%p".to_string(),
+    };
+    DateFormat {
+      locale,
+      date_format: self.date_format.clone(),
+      time_format,
+      header_format: self.header_format.clone(),
+    }
+  }
+}
+
+impl Default for FormatConfig {
+  fn default() -> Self {
+    let date_format = DateFormat::default();
+    FormatConfig {
+      locale: "en".to_string(),
+      date_format: date_format.date_format,
+      time_format: None,
+      header_format: date_format.header_format,
+      clock: Clock::TwentyFourHour,
+    }
+  }
+}
+
 impl Default for AgendaConfig {
   fn default() -> Self {
     AgendaConfig {
       print_week_separator: false,
       print_empty_days: true,
+      event_format: "{index:>4}  {cursor:3}{time:11}  {summary}".to_string(),
+      allday_format: None,
+      continuation_format: None,
+      cursor_format: None,
+      timezone: None,
+    }
+  }
+}
+
+impl Default for IndexConfig {
+  fn default() -> Self {
+    IndexConfig {
+      recur_horizon_days: defaults::RECUR_HORIZON_DAYS,
+      recur_history_days: None,
     }
   }
 }
@@ -73,6 +240,10 @@ impl Default for Config {
   fn default() -> Self {
     Config {
       agenda: AgendaConfig::default(),
+      index: IndexConfig::default(),
+      new: NewConfig::default(),
+      backup: BackupConfig::default(),
+      format: FormatConfig::default(),
       calendars: HashMap::new(),
       local_tz: None,
     }
@@ -104,7 +275,26 @@ mod tests {
       calendars: hashmap!{"sample".to_string() => CalendarConfig { color: Some(81) }},
       agenda: AgendaConfig {
         print_week_separator: true,
-        print_empty_days: false
+        print_empty_days: false,
+        allday_format: Some("{index:>4}  {cursor:3}all day      {summary}".to_string()),
+        ..AgendaConfig::default()
+      },
+      index: IndexConfig {
+        recur_horizon_days: 730,
+        recur_history_days: Some(30),
+      },
+      new: NewConfig {
+        default_calendar: Some("sample".to_string()),
+      },
+      backup: BackupConfig {
+        keep_days: Some(14),
+        keep_operations: None,
+      },
+      format: FormatConfig {
+        locale: "de".to_string(),
+        header_format: "{weekday}, {date}".to_string(),
+        clock: Clock::TwelveHour,
+        ..FormatConfig::default()
       },
       local_tz: None,
     };
@@ -113,6 +303,76 @@ mod tests {
     assert_eq!(expected.calendars.get("sample").unwrap(), cal_config);
   }
 
+  #[test]
+  fn test_check_line_formats() {
+    let mut config = AgendaConfig {
+      event_format: "{index} {nope}".to_string(),
+      allday_format: Some("{summary".to_string()),
+      cursor_format: Some("> {summary}".to_string()),
+      ..AgendaConfig::default()
+    };
+
+    config.check_line_formats();
+
+    assert_eq!(AgendaConfig::default().event_format, config.event_format);
+    assert_eq!(None, config.allday_format);
+    assert_eq!(Some("> {summary}".to_string()), config.cursor_format);
+  }
+
+  #[test]
+  fn test_get_line_format() {
+    let config = AgendaConfig {
+      cursor_format: Some("cursor".to_string()),
+      allday_format: Some("allday".to_string()),
+      ..AgendaConfig::default()
+    };
+
+    assert_eq!("cursor", config.get_line_format(true, true, true));
+    assert_eq!("allday", config.get_line_format(false, true, true));
+    assert_eq!(config.event_format, config.get_line_format(false, false, true));
+  }
+
+  #[test]
+  fn test_check_formats() {
+    let mut config = FormatConfig {
+      date_format: "%Y-%m-%".to_string(),
+      time_format: Some("%Q".to_string()),
+      ..FormatConfig::default()
+    };
+
+    config.check_formats();
+
+    assert_eq!(DateFormat::default().date_format, config.date_format);
+    assert_eq!(None, config.time_format);
+    assert_eq!("%H:%M", config.get_date_format().time_format);
+
+    let mut config = FormatConfig {
+      date_format: "%d.%m.%Y".to_string(),
+      time_format: Some("%H.%M".to_string()),
+      ..FormatConfig::default()
+    };
+
+    config.check_formats();
+
+    assert_eq!("%d.%m.%Y", config.date_format);
+    assert_eq!(Some("%H.%M".to_string()), config.time_format);
+  }
+
+  #[test]
+  fn test_get_date_format() {
+    let config = FormatConfig {
+      locale: "de_DE.UTF-8".to_string(),
+      clock: Clock::TwelveHour,
+      ..FormatConfig::default()
+    };
+
+    let date_format = config.get_date_format();
+
+    assert_eq!(Locale::De, date_format.locale);
+    assert_eq!("%I:%M%p", date_format.time_format);
+    assert_eq!("%Y-%m-%d", date_format.date_format);
+  }
+
   #[test]
   fn test_get_style_for_calendar() {
     let config = CalendarConfig { color: Some(81) };
@@ -1,16 +1,25 @@
 use std::path::{Path,PathBuf};
-use std::cell::RefCell;
+use std::cell::{Cell,RefCell};
 use std::env;
 
+use crate::icalwrap::IcalTimeZone;
+use crate::utils::dateformat::DateFormat;
+
 pub static DATADIR: &str = ".khaleesi";
 pub static INDEXDIR: &str = "index";
 pub static SEQFILE: &str  = "seq";
 pub static CURSORFILE: &str  = "cursor";
 pub static CALDIR: &str  = "cal";
 pub static BACKUPDIR: &str  = "backup";
+pub static JOURNALFILE: &str  = "journal";
+pub static RECUR_HORIZON_DAYS: u32 = 365;
 
 thread_local! {
-  static KHALEESI_DIR: RefCell<PathBuf> = RefCell::new(env::current_dir().unwrap())
+  static KHALEESI_DIR: RefCell<PathBuf> = RefCell::new(env::current_dir().unwrap());
+  static RECUR_HORIZON: Cell<(u32, Option<u32>)> = Cell::new((RECUR_HORIZON_DAYS, None));
+  static BACKUP_RETENTION: Cell<(Option<u32>, Option<usize>)> = Cell::new((None, None));
+  static DATE_FORMAT: RefCell<DateFormat> = RefCell::new(DateFormat::default());
+  static DISPLAY_TIMEZONE: RefCell<Option<String>> = RefCell::new(None);
 }
 
 fn get_khaleesi_dir() -> PathBuf {
@@ -24,6 +33,56 @@ pub fn set_khaleesi_dir(path: &Path) {
   });
 }
 
+/// How many days into the future and (optionally) into the past recurring
+/// events are unrolled, relative to today.
+pub fn get_recur_horizon() -> (u32, Option<u32>) {
+  RECUR_HORIZON.with(|horizon| horizon.get())
+}
+
+pub fn set_recur_horizon(future_days: u32, past_days: Option<u32>) {
+  RECUR_HORIZON.with(|horizon| horizon.set((future_days, past_days)));
+}
+
+/// For how many days and how many operations backups are kept. Backups are
+/// kept forever if neither is set.
+pub fn get_backup_retention() -> (Option<u32>, Option<usize>) {
+  BACKUP_RETENTION.with(|retention| retention.get())
+}
+
+pub fn set_backup_retention(keep_days: Option<u32>, keep_operations: Option<usize>) {
+  BACKUP_RETENTION.with(|retention| retention.set((keep_days, keep_operations)));
+}
+
+/// How dates and times are printed.
+pub fn get_date_format() -> DateFormat {
+  DATE_FORMAT.with(|date_format| date_format.borrow().clone())
+}
+
+pub fn set_date_format(date_format: DateFormat) {
+  DATE_FORMAT.with(|cell| {
+    *cell.borrow_mut() = date_format;
+  });
+}
+
+/// The timezone events are shown in, the local timezone unless another one
+/// was set.
+pub fn get_display_timezone() -> IcalTimeZone {
+  DISPLAY_TIMEZONE.with(|timezone| match &*timezone.borrow() {
+    Some(name) => IcalTimeZone::from_name(name).unwrap_or_else(|_| IcalTimeZone::local()),
+    None => IcalTimeZone::local(),
+  })
+}
+
+pub fn set_display_timezone(name: Option<&str>) -> Result<(), String> {
+  if let Some(name) = name {
+    IcalTimeZone::from_name(name)?;
+  }
+  DISPLAY_TIMEZONE.with(|timezone| {
+    *timezone.borrow_mut() = name.map(|name| name.to_string());
+  });
+  Ok(())
+}
+
 pub fn get_datafile(filename: &str) -> PathBuf {
   let mut dir = get_khaleesi_dir();
   dir.push(DATADIR);
@@ -45,6 +104,20 @@ pub fn get_cursorfile() -> PathBuf {
   dir
 }
 
+pub fn get_journalfile() -> PathBuf {
+  let mut dir = get_khaleesi_dir();
+  dir.push(DATADIR);
+  dir.push(JOURNALFILE);
+  dir
+}
+
+pub fn get_journallockfile() -> PathBuf {
+  let mut dir = get_khaleesi_dir();
+  dir.push(DATADIR);
+  dir.push("journal-lock");
+  dir
+}
+
 pub fn get_configfile() -> PathBuf {
   let mut dir = get_khaleesi_dir();
   dir.push(DATADIR);
@@ -88,6 +161,13 @@ pub fn get_indextimefile() -> PathBuf {
   dir
 }
 
+pub fn get_indexmanifestfile() -> PathBuf {
+  let mut dir = get_khaleesi_dir();
+  dir.push(DATADIR);
+  dir.push("index-manifest");
+  dir
+}
+
 pub fn get_caldir() -> PathBuf {
   let mut dir = get_khaleesi_dir();
   dir.push(DATADIR);
@@ -38,8 +38,8 @@ fn ask_continue_editing(error: &[String]) -> bool {
   println!("Calendar contains errors:\n{}", error.join("\n"));
   println!("Continue editing? y/n:");
 
-  match stdioutils::read_single_char_from_stdin().unwrap() {
-    'y' => true,
+  match stdioutils::read_single_char_from_terminal() {
+    Ok('y') => true,
     _ => false
   }
 }
@@ -81,3 +81,10 @@ impl From<::std::io::Error> for KhError {
     KhError::new(&description, Some(Box::new(e)))
   }
 }
+
+impl From<serde_json::Error> for KhError {
+  fn from(e: serde_json::Error) -> KhError {
+    let description = e.to_string();
+    KhError::new(&description, Some(Box::new(e)))
+  }
+}
@@ -1,6 +1,6 @@
 use std::ops::{Add,Deref};
 use std::ffi::{CStr,CString};
-use chrono::{Date,DateTime,TimeZone,Utc,Local};
+use chrono::{Date,DateTime,NaiveDate,NaiveDateTime,TimeZone,Utc,Local};
 use crate::ical;
 use crate::utils::dateutil;
 use super::IcalTimeZone;
@@ -89,6 +89,38 @@ impl IcalTime {
     IcalTime { time }
   }
 
+  /// The wall clock date and time in `timezone`. Dates have no time to
+  /// convert, they are taken as they are.
+  pub fn to_naive_datetime(&self, timezone: &IcalTimeZone) -> NaiveDateTime {
+    let time = if self.is_date() {
+      self.clone()
+    } else {
+      IcalTime::from_timestamp(self.timestamp()).with_timezone(timezone)
+    };
+    NaiveDate::from_ymd(time.year, time.month as u32, time.day as u32)
+      .and_hms(time.hour as u32, time.minute as u32, time.second as u32)
+  }
+
+  /// ISO 8601 representation, with the UTC offset of the timezone if there
+  /// is one. Dates and floating times have no offset.
+  pub fn to_iso8601(&self) -> String {
+    let time = &self.time;
+    let date = format!("{:04}-{:02}-{:02}", time.year, time.month, time.day);
+    if self.is_date() {
+      return date;
+    }
+    let datetime = format!("{}T{:02}:{:02}:{:02}", date, time.hour, time.minute, time.second);
+    match self.get_timezone() {
+      Some(timezone) => {
+        let offset = timezone.get_offset_at_time(self);
+        let sign = if offset < 0 { '-' } else { '+' };
+        let offset = offset.abs();
+        format!("{}{}{:02}:{:02}", datetime, sign, offset / 3600, offset % 3600 / 60)
+      }
+      None => datetime,
+    }
+  }
+
   pub fn pred(&self) -> IcalTime {
     let mut time = self.time;
     time.day -= 1;
@@ -254,6 +286,26 @@ mod tests {
     assert_eq!("20130101T020203", time.to_string());
   }
 
+  #[test]
+  fn test_to_iso8601() {
+    let utc = IcalTime::utc();
+    let tz = IcalTimeZone::from_name("US/Eastern").unwrap();
+
+    assert_eq!("2013-01-01T01:02:03+00:00", utc.to_iso8601());
+    assert_eq!("2012-12-31T20:02:03-05:00", utc.with_timezone(&tz).to_iso8601());
+    assert_eq!("2013-01-01", IcalTime::floating_ymd(2013, 1, 1).to_iso8601());
+    assert_eq!("2013-01-01T10:30:00", IcalTime::floating_ymd(2013, 1, 1).and_hms(10, 30, 0).to_iso8601());
+  }
+
+  #[test]
+  fn test_to_naive_datetime() {
+    let utc = IcalTime::utc();
+    let tz = IcalTimeZone::from_name("America/New_York").unwrap();
+
+    assert_eq!(NaiveDate::from_ymd(2012, 12, 31).and_hms(20, 2, 3), utc.to_naive_datetime(&tz));
+    assert_eq!(NaiveDate::from_ymd(2013, 1, 1).and_hms(0, 0, 0), IcalTime::floating_ymd(2013, 1, 1).to_naive_datetime(&tz));
+  }
+
   #[test]
   fn test_parse() {
     let time = "20130101T010203Z".parse::<IcalTime>().unwrap();
@@ -56,6 +56,13 @@ impl IcalTimeZone {
     }
   }
 
+  pub fn get_tzid(&self) -> String {
+    unsafe {
+      let tzid = ical::icaltimezone_get_tzid(self.timezone);
+      CStr::from_ptr(tzid).to_string_lossy().into_owned()
+    }
+  }
+
   pub fn get_offset_at_time(&self, time: &IcalTime) -> i32 {
     let mut icaltime = **time;
     let mut is_dst = 0;
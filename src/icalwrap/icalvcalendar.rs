@@ -149,7 +149,11 @@ impl IcalVCalendar {
   pub fn with_dtstart(self, dtstart: &IcalTime) -> Self {
     let event = self.get_principal_event();
     unsafe {
-      let timezone = event.get_dtstart().and_then(|x| x.get_timezone());
+      let timezone = if dtstart.is_date() {
+        None
+      } else {
+        event.get_dtstart().and_then(|x| x.get_timezone())
+      };
       match timezone {
         Some(timezone) => {
           ical::icalcomponent_set_dtstart(event.get_ptr(), *dtstart.with_timezone(&timezone));
@@ -165,7 +169,11 @@ impl IcalVCalendar {
   pub fn with_dtend(self, dtend: &IcalTime) -> Self {
     let event = self.get_principal_event();
     unsafe {
-      let timezone = event.get_dtend().and_then(|x| x.get_timezone());
+      let timezone = if dtend.is_date() {
+        None
+      } else {
+        event.get_dtend().and_then(|x| x.get_timezone())
+      };
       match timezone {
         Some(timezone) => {
           ical::icalcomponent_set_dtend(event.get_ptr(), *dtend.with_timezone(&timezone));
@@ -196,6 +204,50 @@ impl IcalVCalendar {
     self
   }
 
+  pub fn with_description(self, description: &str) -> Self {
+    let event = self.get_principal_event();
+    unsafe {
+      let c_str = CString::new(description).unwrap();
+      ical::icalcomponent_set_description(event.get_ptr(), c_str.as_ptr());
+    }
+    self
+  }
+
+  pub fn with_category(self, category: &str) -> Self {
+    let event = self.get_principal_event();
+    unsafe {
+      let c_str = CString::new(category).unwrap();
+      let prop = ical::icalproperty_new_categories(c_str.as_ptr());
+      ical::icalcomponent_add_property(event.get_ptr(), prop);
+    }
+    self
+  }
+
+  pub fn with_url(self, url: &str) -> Self {
+    let event = self.get_principal_event();
+    unsafe {
+      let c_str = CString::new(url).unwrap();
+      let prop = ical::icalproperty_new_url(c_str.as_ptr());
+      ical::icalcomponent_add_property(event.get_ptr(), prop);
+    }
+    self
+  }
+
+  pub fn with_rrule(self, rrule: &str) -> Result<Self, String> {
+    {
+      let event = self.get_principal_event();
+      let c_str = CString::new(format!("RRULE:{}", rrule)).unwrap();
+      unsafe {
+        let prop = ical::icalproperty_new_from_string(c_str.as_ptr());
+        if prop.is_null() {
+          return Err(format!("Invalid recurrence rule {}", rrule));
+        }
+        ical::icalcomponent_add_property(event.get_ptr(), prop);
+      }
+    }
+    Ok(self)
+  }
+
   pub fn with_last_modified_now(self) -> Self {
     let event = self.get_principal_event();
     unsafe {
@@ -212,6 +264,86 @@ impl IcalVCalendar {
     self
   }
 
+  pub fn with_sequence_incremented(self) -> Self {
+    let event = self.get_principal_event();
+    unsafe {
+      let sequence = ical::icalcomponent_get_sequence(event.get_ptr());
+      ical::icalcomponent_set_sequence(event.get_ptr(), sequence + 1);
+    }
+    self
+  }
+
+  /// Replaces all properties called `property_name` in the principal event
+  /// with a single one holding `value`.
+  pub fn with_property(self, property_name: &str, value: &str) -> Result<Self, String> {
+    let property_name = property_name.to_uppercase();
+    let c_name = CString::new(property_name.as_str()).unwrap();
+    let c_value = CString::new(value).unwrap();
+    let c_value_kind = CString::new("NO").unwrap();
+    {
+      let event = self.get_principal_event();
+      unsafe {
+        let kind = ical::icalproperty_string_to_kind(c_name.as_ptr());
+        if kind == ical::icalproperty_kind_ICAL_NO_PROPERTY {
+          return Err(format!("Unknown property {}", property_name));
+        }
+        let prop = ical::icalproperty_new(kind);
+        if kind == ical::icalproperty_kind_ICAL_X_PROPERTY {
+          ical::icalproperty_set_x_name(prop, c_name.as_ptr());
+        }
+        ical::icalproperty_set_value_from_string(prop, c_value.as_ptr(), c_value_kind.as_ptr());
+        if ical::icalproperty_get_value(prop).is_null() {
+          ical::icalproperty_free(prop);
+          return Err(format!("Invalid value for {}: {}", property_name, value));
+        }
+        IcalVCalendar::remove_properties_by_name(event.get_ptr(), &property_name);
+        ical::icalcomponent_add_property(event.get_ptr(), prop);
+      }
+    }
+    Ok(self)
+  }
+
+  /// Removes all properties called `property_name` from the principal event.
+  /// Unlike `with_remove_property`, this leaves other components alone.
+  pub fn with_unset_property(self, property_name: &str) -> (Self, usize) {
+    let count = {
+      let event = self.get_principal_event();
+      unsafe { IcalVCalendar::remove_properties_by_name(event.get_ptr(), property_name) }
+    };
+    (self, count)
+  }
+
+  pub fn get_categories(&self) -> Vec<String> {
+    self.get_principal_event().get_categories()
+  }
+
+  pub fn with_categories(self, categories: &[String]) -> Self {
+    {
+      let event = self.get_principal_event();
+      unsafe {
+        IcalVCalendar::remove_properties_by_name(event.get_ptr(), "CATEGORIES");
+      }
+    }
+    categories.iter().fold(self, |cal, category| cal.with_category(category))
+  }
+
+  unsafe fn remove_properties_by_name(comp: *mut ical::icalcomponent, property_name: &str) -> usize {
+    let mut matching = Vec::new();
+    let mut prop = ical::icalcomponent_get_first_property(comp, ical::icalproperty_kind_ICAL_ANY_PROPERTY);
+    while !prop.is_null() {
+      let name = CStr::from_ptr(ical::icalproperty_get_property_name(prop)).to_string_lossy();
+      if name.eq_ignore_ascii_case(property_name) {
+        matching.push(prop);
+      }
+      prop = ical::icalcomponent_get_next_property(comp, ical::icalproperty_kind_ICAL_ANY_PROPERTY);
+    }
+    for prop in &matching {
+      ical::icalcomponent_remove_property(comp, *prop);
+      ical::icalproperty_free(*prop);
+    }
+    matching.len()
+  }
+
   pub fn with_remove_property(self, property_name: &str) -> (Self, usize) {
     let property_kind = unsafe {
       let c_str = CString::new(property_name).unwrap();
@@ -222,6 +354,207 @@ impl IcalVCalendar {
     (self, count)
   }
 
+  /// Excludes the occurrence at `recurrence_id` from the recurrence of the
+  /// principal event, and drops an override of that occurrence if there is one.
+  pub fn with_exdate(self, recurrence_id: &IcalTime) -> Result<Self, String> {
+    {
+      let event = self.get_principal_event();
+      unsafe {
+        let prop = IcalVCalendar::new_time_property("EXDATE", recurrence_id)?;
+        ical::icalcomponent_add_property(event.get_ptr(), prop);
+      }
+    }
+    let timestamp = recurrence_id.timestamp();
+    Ok(self.with_overrides_removed(|override_id| override_id.timestamp() == timestamp))
+  }
+
+  /// Adds a VEVENT with a RECURRENCE-ID that overrides the occurrence at
+  /// `recurrence_id`. It starts out as a copy of the principal event without
+  /// recurrence rules. Does nothing if there already is such an override.
+  pub fn with_recurrence_override(self, recurrence_id: &IcalTime) -> Result<Self, String> {
+    let timestamp = recurrence_id.timestamp();
+    let has_override = unsafe {
+      self
+        .get_override_components()
+        .iter()
+        .any(|(_, override_id)| override_id.timestamp() == timestamp)
+    };
+    if has_override {
+      return Ok(self);
+    }
+
+    {
+      let event = self.get_principal_event();
+      let dtend = match event.get_duration() {
+        Some(duration) if event.has_property_dtend() => Some(recurrence_id.clone() + duration),
+        _ => None,
+      };
+      unsafe {
+        let prop = IcalVCalendar::new_time_property("RECURRENCE-ID", recurrence_id)?;
+        let comp = ical::icalcomponent_new_clone(event.get_ptr());
+        for property_name in &["RRULE", "RDATE", "EXDATE", "EXRULE"] {
+          IcalVCalendar::remove_properties_by_name(comp, property_name);
+        }
+        ical::icalcomponent_add_property(comp, prop);
+        ical::icalcomponent_set_dtstart(comp, **recurrence_id);
+        if let Some(dtend) = dtend {
+          ical::icalcomponent_set_dtend(comp, *dtend);
+        }
+        ical::icalcomponent_add_component(self.get_ptr(), comp);
+      }
+    }
+    Ok(self)
+  }
+
+  /// Ends the recurrence of the principal event right before the occurrence
+  /// at `recurrence_id`, and drops overrides of this and later occurrences.
+  pub fn with_recurrence_end(self, recurrence_id: &IcalTime) -> Result<Self, String> {
+    let until = if recurrence_id.is_date() {
+      recurrence_id.pred()
+    } else {
+      IcalTime::from_timestamp(recurrence_id.timestamp() - 1)
+    };
+    {
+      let event = self.get_principal_event();
+      let rrule = event
+        .get_property(ical::icalproperty_kind_ICAL_RRULE_PROPERTY)
+        .ok_or("event does not recur")?;
+      unsafe {
+        let mut recur = ical::icalproperty_get_rrule(rrule.ptr);
+        recur.until = *until;
+        recur.count = 0;
+        ical::icalproperty_set_rrule(rrule.ptr, recur);
+      }
+    }
+    let timestamp = recurrence_id.timestamp();
+    Ok(self.with_overrides_removed(|override_id| override_id.timestamp() >= timestamp))
+  }
+
+  /// Moves the start of the principal event to the occurrence at
+  /// `recurrence_id`, so that its recurrence only covers this and later
+  /// occurrences. A COUNT is reduced by the RRULE occurrences before it, and
+  /// EXDATEs, RDATEs and overrides of earlier occurrences are dropped.
+  pub fn with_recurrence_start(self, recurrence_id: &IcalTime) -> Result<Self, String> {
+    let timestamp = recurrence_id.timestamp();
+    {
+      let event = self.get_principal_event();
+      let dtstart = event.get_dtstart().ok_or("event has no start")?;
+      let dtend = match event.get_duration() {
+        Some(duration) if event.has_property_dtend() => Some(recurrence_id.clone() + duration),
+        _ => None,
+      };
+      let rrule = event
+        .get_property(ical::icalproperty_kind_ICAL_RRULE_PROPERTY)
+        .ok_or("event does not recur")?;
+      unsafe {
+        let mut recur = ical::icalproperty_get_rrule(rrule.ptr);
+        if recur.count > 0 {
+          let skipped = IcalVCalendar::count_rrule_occurrences_before(recur, &dtstart, timestamp);
+          recur.count = std::cmp::max(recur.count - skipped, 1);
+          ical::icalproperty_set_rrule(rrule.ptr, recur);
+        }
+        IcalVCalendar::remove_recurrence_dates_before(event.get_ptr(), timestamp);
+        ical::icalcomponent_set_dtstart(event.get_ptr(), **recurrence_id);
+        if let Some(dtend) = dtend {
+          ical::icalcomponent_set_dtend(event.get_ptr(), *dtend);
+        }
+      }
+    }
+    Ok(self.with_overrides_removed(|override_id| override_id.timestamp() < timestamp))
+  }
+
+  /// Counts the occurrences of the RRULE alone, without EXDATEs and RDATEs,
+  /// that start before `timestamp`.
+  unsafe fn count_rrule_occurrences_before(recur: ical::icalrecurrencetype, dtstart: &IcalTime, timestamp: i64) -> i32 {
+    let iterator = ical::icalrecur_iterator_new(recur, **dtstart);
+    if iterator.is_null() {
+      return 0;
+    }
+    let mut count = 0;
+    loop {
+      let occurrence = ical::icalrecur_iterator_next(iterator);
+      if ical::icaltime_is_null_time(occurrence) != 0 || IcalTime::from(occurrence).timestamp() >= timestamp {
+        break;
+      }
+      count += 1;
+    }
+    ical::icalrecur_iterator_free(iterator);
+    count
+  }
+
+  /// Removes the EXDATEs and RDATEs of `comp` that lie before `timestamp`.
+  unsafe fn remove_recurrence_dates_before(comp: *mut ical::icalcomponent, timestamp: i64) {
+    let exdate_kind = ical::icalproperty_kind_ICAL_EXDATE_PROPERTY;
+    let rdate_kind = ical::icalproperty_kind_ICAL_RDATE_PROPERTY;
+    let mut matching = Vec::new();
+    for &kind in &[exdate_kind, rdate_kind] {
+      let mut prop = ical::icalcomponent_get_first_property(comp, kind);
+      while !prop.is_null() {
+        let time = if kind == exdate_kind {
+          ical::icalproperty_get_exdate(prop)
+        } else {
+          let rdate = ical::icalproperty_get_rdate(prop);
+          if ical::icaltime_is_null_time(rdate.time) == 0 { rdate.time } else { rdate.period.start }
+        };
+        if IcalTime::from(time).timestamp() < timestamp {
+          matching.push(prop);
+        }
+        prop = ical::icalcomponent_get_next_property(comp, kind);
+      }
+    }
+    for prop in &matching {
+      ical::icalcomponent_remove_property(comp, *prop);
+      ical::icalproperty_free(*prop);
+    }
+  }
+
+  fn with_overrides_removed<F: Fn(&IcalTime) -> bool>(self, should_remove: F) -> Self {
+    unsafe {
+      for (comp, recurrence_id) in self.get_override_components() {
+        if should_remove(&recurrence_id) {
+          ical::icalcomponent_remove_component(self.get_ptr(), comp);
+          ical::icalcomponent_free(comp);
+        }
+      }
+    }
+    self
+  }
+
+  /// all VEVENT components with a RECURRENCE-ID, along with that id
+  unsafe fn get_override_components(&self) -> Vec<(*mut ical::icalcomponent, IcalTime)> {
+    let vevent_kind = ical::icalcomponent_kind_ICAL_VEVENT_COMPONENT;
+    let mut overrides = Vec::new();
+    let mut comp = ical::icalcomponent_get_first_component(self.get_ptr(), vevent_kind);
+    while !comp.is_null() {
+      let recurrence_id = ical::icalcomponent_get_recurrenceid(comp);
+      if ical::icaltime_is_null_time(recurrence_id) == 0 {
+        overrides.push((comp, IcalTime::from(recurrence_id)));
+      }
+      comp = ical::icalcomponent_get_next_component(self.get_ptr(), vevent_kind);
+    }
+    overrides
+  }
+
+  /// Creates a property like EXDATE or RECURRENCE-ID that refers to `time`,
+  /// with a VALUE=DATE or TZID parameter where needed.
+  unsafe fn new_time_property(property_name: &str, time: &IcalTime) -> Result<*mut ical::icalproperty, String> {
+    let parameter = if time.is_date() {
+      ";VALUE=DATE".to_string()
+    } else {
+      match time.get_timezone().map(|timezone| timezone.get_tzid()) {
+        Some(ref tzid) if tzid != "UTC" => format!(";TZID={}", tzid),
+        _ => "".to_string(),
+      }
+    };
+    let c_str = CString::new(format!("{}{}:{}", property_name, parameter, time)).unwrap();
+    let prop = ical::icalproperty_new_from_string(c_str.as_ptr());
+    if prop.is_null() {
+      Err(format!("Invalid {} {}", property_name, time))
+    } else {
+      Ok(prop)
+    }
+  }
+
   pub fn with_keep_uid(self, uid_to_keep: &str) -> Self {
     unsafe {
       ical::icalcomponent_get_first_component(
@@ -250,6 +583,157 @@ impl IcalVCalendar {
     }
   }
 
+  /// The UIDs of all events, in the order in which they first appear.
+  pub fn get_event_uids(&self) -> Result<Vec<String>, String> {
+    let mut uids: Vec<String> = Vec::new();
+    for event in self.events_iter() {
+      let uid = unsafe {
+        let uid_ptr = ical::icalcomponent_get_uid(event.get_ptr());
+        if uid_ptr.is_null() {
+          return Err("missing required property: UID".to_string());
+        }
+        CStr::from_ptr(uid_ptr).to_string_lossy().into_owned()
+      };
+      if !uids.contains(&uid) {
+        uids.push(uid);
+      }
+    }
+    Ok(uids)
+  }
+
+  /// Removes the VTIMEZONE components that no event refers to.
+  pub fn with_unused_timezones_removed(self) -> Self {
+    unsafe {
+      let used_tzids = self.get_used_tzids();
+      let vtimezone_kind = ical::icalcomponent_kind_ICAL_VTIMEZONE_COMPONENT;
+      let mut timezones = Vec::new();
+      let mut comp = ical::icalcomponent_get_first_component(self.get_ptr(), vtimezone_kind);
+      while !comp.is_null() {
+        timezones.push(comp);
+        comp = ical::icalcomponent_get_next_component(self.get_ptr(), vtimezone_kind);
+      }
+
+      for timezone in timezones {
+        match IcalVCalendar::get_vtimezone_tzid(timezone) {
+          Some(ref tzid) if !used_tzids.contains(tzid) => {
+            ical::icalcomponent_remove_component(self.get_ptr(), timezone);
+            ical::icalcomponent_free(timezone);
+          }
+          _ => (),
+        }
+      }
+    }
+    self
+  }
+
+  unsafe fn get_vtimezone_tzid(timezone: *mut ical::icalcomponent) -> Option<String> {
+    let tzid_prop = ical::icalcomponent_get_first_property(timezone, ical::icalproperty_kind_ICAL_TZID_PROPERTY);
+    if tzid_prop.is_null() {
+      None
+    } else {
+      Some(CStr::from_ptr(ical::icalproperty_get_tzid(tzid_prop)).to_string_lossy().into_owned())
+    }
+  }
+
+  /// Adds copies of all components of `other`. A VTIMEZONE is skipped if
+  /// there already is one with the same TZID.
+  pub fn with_components_from(self, other: &IcalVCalendar) -> Self {
+    unsafe {
+      let mut tzids = Vec::new();
+      let vtimezone_kind = ical::icalcomponent_kind_ICAL_VTIMEZONE_COMPONENT;
+      let mut comp = ical::icalcomponent_get_first_component(self.get_ptr(), vtimezone_kind);
+      while !comp.is_null() {
+        tzids.extend(IcalVCalendar::get_vtimezone_tzid(comp));
+        comp = ical::icalcomponent_get_next_component(self.get_ptr(), vtimezone_kind);
+      }
+
+      let any_kind = ical::icalcomponent_kind_ICAL_ANY_COMPONENT;
+      let mut comp = ical::icalcomponent_get_first_component(other.get_ptr(), any_kind);
+      while !comp.is_null() {
+        let is_known_timezone = ical::icalcomponent_isa(comp) == vtimezone_kind
+          && IcalVCalendar::get_vtimezone_tzid(comp).map_or(false, |tzid| tzids.contains(&tzid));
+        if !is_known_timezone {
+          tzids.extend(IcalVCalendar::get_vtimezone_tzid(comp));
+          ical::icalcomponent_add_component(self.get_ptr(), ical::icalcomponent_new_clone(comp));
+        }
+        comp = ical::icalcomponent_get_next_component(other.get_ptr(), any_kind);
+      }
+    }
+    self
+  }
+
+  /// Turns the occurrence at `recurrence_id` into a single event that does
+  /// not recur. It gets a UID of its own, so that other occurrences of the
+  /// same series do not collide with it.
+  pub fn with_occurrence_flattened(self, recurrence_id: &IcalTime) -> Result<Self, String> {
+    let timestamp = recurrence_id.timestamp();
+    let has_override = unsafe {
+      self
+        .get_override_components()
+        .iter()
+        .any(|(_, override_id)| override_id.timestamp() == timestamp)
+    };
+
+    let cal = if has_override {
+      unsafe {
+        let master = self.get_principal_event();
+        // without a parent, the component is freed when master is dropped
+        if master.get_recurrence_id().is_none() {
+          ical::icalcomponent_remove_component(self.get_ptr(), master.get_ptr());
+        }
+      }
+      let cal = self.with_overrides_removed(|override_id| override_id.timestamp() != timestamp);
+      unsafe {
+        IcalVCalendar::remove_properties_by_name(cal.get_principal_event().get_ptr(), "RECURRENCE-ID");
+      }
+      cal
+    } else {
+      let cal = self.with_overrides_removed(|_| true);
+      {
+        let event = cal.get_principal_event();
+        let dtend = match event.get_duration() {
+          Some(duration) if event.has_property_dtend() => Some(recurrence_id.clone() + duration),
+          _ => None,
+        };
+        unsafe {
+          for property_name in &["RRULE", "RDATE", "EXDATE", "EXRULE"] {
+            IcalVCalendar::remove_properties_by_name(event.get_ptr(), property_name);
+          }
+          ical::icalcomponent_set_dtstart(event.get_ptr(), **recurrence_id);
+          if let Some(dtend) = dtend {
+            ical::icalcomponent_set_dtend(event.get_ptr(), *dtend);
+          }
+        }
+      }
+      cal
+    };
+
+    let uid = format!("{}-{}", cal.get_uid(), recurrence_id);
+    cal.with_uid(&uid)
+  }
+
+  /// all TZID parameters of all event properties
+  unsafe fn get_used_tzids(&self) -> Vec<String> {
+    let mut tzids = Vec::new();
+    for event in self.events_iter() {
+      let any_kind = ical::icalproperty_kind_ICAL_ANY_PROPERTY;
+      let mut prop = ical::icalcomponent_get_first_property(event.get_ptr(), any_kind);
+      while !prop.is_null() {
+        let param = ical::icalproperty_get_first_parameter(prop, ical::icalparameter_kind_ICAL_TZID_PARAMETER);
+        if !param.is_null() {
+          tzids.push(CStr::from_ptr(ical::icalparameter_get_tzid(param)).to_string_lossy().into_owned());
+        }
+        prop = ical::icalcomponent_get_next_property(event.get_ptr(), any_kind);
+      }
+    }
+    tzids
+  }
+
+  pub fn get_sequence(&self) -> i32 {
+    let event = self.get_principal_event();
+    unsafe { ical::icalcomponent_get_sequence(event.get_ptr()) }
+  }
+
   pub fn get_path_as_string(&self) -> Option<String> {
     self.path.as_ref().map(|path| format!("{}", path.display()))
   }
@@ -267,22 +751,51 @@ impl IcalVCalendar {
     IcalEventIter::from_vcalendar(self)
   }
 
+  /// The first VEVENT that is not an override of a single occurrence, or
+  /// just the first VEVENT if there are only overrides.
   pub fn get_first_event(&self) -> IcalVEvent {
-    let event = unsafe {
-      ical::icalcomponent_get_first_component(
-        self.get_ptr(),
-        ical::icalcomponent_kind_ICAL_VEVENT_COMPONENT,
-      )
-    };
     if self.events_iter().unique_uid_count() > 1 {
       warn!(
         "More than one event in file: {}",
         self.get_path_as_string().unwrap_or_else(|| "".to_string())
       )
     }
+    if let Some(master) = self.events_iter().find(|event| event.get_recurrence_id().is_none()) {
+      return master;
+    }
+    let event = unsafe {
+      ical::icalcomponent_get_first_component(
+        self.get_ptr(),
+        ical::icalcomponent_kind_ICAL_VEVENT_COMPONENT,
+      )
+    };
     IcalVEvent::from_ptr_with_parent(event, self)
   }
 
+  /// All VEVENTs that override single occurrences of the principal event.
+  pub fn get_override_events(&self) -> Vec<IcalVEvent> {
+    self
+      .events_iter()
+      .filter(|event| event.get_recurrence_id().is_some())
+      .collect()
+  }
+
+  /// The event that takes place at `time`: an override that was moved to that
+  /// time if there is one, the principal event otherwise.
+  pub fn get_khevent_at(&self, time: Option<&IcalTime>) -> KhEvent {
+    if let Some(time) = time {
+      let timestamp = time.timestamp();
+      let moved_override = self
+        .get_override_events()
+        .into_iter()
+        .find(|event| event.get_dtstart().map_or(false, |start| start.timestamp() == timestamp));
+      if let Some(event) = moved_override {
+        return KhEvent::from_event(event);
+      }
+    }
+    KhEvent::from_event_with_timestamp(self.get_first_event(), time.cloned())
+  }
+
   pub(in crate::icalwrap) fn get_principal_event(&self) -> IcalVEvent {
     let mut event = self.get_first_event();
     if let Some(ref timestamp) = self.instance_timestamp {
@@ -544,6 +1057,108 @@ mod tests {
     assert_eq!(summary, event.get_summary().unwrap())
   }
 
+  #[test]
+  fn test_with_description() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+
+    let description = "test";
+    let new_cal = cal.with_description(description);
+
+    let event = new_cal.get_principal_khevent();
+    assert_eq!(description, event.get_description().unwrap())
+  }
+
+  #[test]
+  fn test_with_category_url() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+
+    let new_cal = cal
+      .with_category("music")
+      .with_category("outdoor")
+      .with_url("https://example.com/jazz");
+
+    let event = new_cal.get_principal_khevent();
+    let categories: Vec<String> = event.get_properties_by_name("CATEGORIES").iter().map(|prop| prop.get_value()).collect();
+    assert_eq!(vec!("music", "outdoor"), categories);
+    assert_eq!("https://example.com/jazz", event.get_properties_by_name("URL")[0].get_value());
+  }
+
+  #[test]
+  fn test_with_property() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+
+    let new_cal = cal
+      .with_property("summary", "new summary").unwrap()
+      .with_property("X-KHALEESI-TEST", "yes").unwrap();
+
+    let event = new_cal.get_principal_khevent();
+    assert_eq!("new summary", event.get_summary().unwrap());
+    assert_eq!(1, event.get_properties_by_name("SUMMARY").len());
+    assert!(new_cal.to_string().contains("X-KHALEESI-TEST:yes"));
+  }
+
+  #[test]
+  fn test_with_property_invalid() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+    assert!(cal.with_property("NOT A PROPERTY", "x").is_err());
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+    assert!(cal.with_property("DTSTART", "quatsch").is_err());
+  }
+
+  #[test]
+  fn test_with_unset_property() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+
+    let (new_cal, count) = cal.with_unset_property("LOCATION");
+
+    assert_eq!(1, count);
+    assert_eq!(None, new_cal.get_principal_khevent().get_location());
+  }
+
+  #[test]
+  fn test_categories() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
+    assert_eq!(vec!("MEETING", "PROJECT"), cal.get_categories());
+
+    let new_cal = cal.with_categories(&["PROJECT".to_string(), "work".to_string()]);
+    assert_eq!(vec!("PROJECT", "work"), new_cal.get_categories());
+  }
+
+  #[test]
+  fn test_with_sequence_incremented() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
+
+    let new_cal = cal.with_sequence_incremented();
+
+    assert!(new_cal.to_string().contains("SEQUENCE:1"));
+  }
+
+  #[test]
+  fn test_with_rrule() {
+    testdata::setup();
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
+
+    let new_cal = cal.with_rrule("FREQ=WEEKLY;COUNT=3").unwrap();
+
+    let event = new_cal.get_principal_khevent();
+    assert!(event.is_recur_master());
+    assert_eq!(3, event.get_recur_datetimes().len());
+  }
+
+  #[test]
+  fn test_with_dtstart_date() {
+    testdata::setup();
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
+
+    let date = IcalTime::floating_ymd(2018, 1, 1);
+    let new_cal = cal.with_dtstart(&date).with_dtend(&date.succ());
+
+    let event = new_cal.get_principal_khevent();
+    assert!(event.get_start().unwrap().is_date());
+    assert!(event.get_start().unwrap().get_timezone().is_none());
+    assert!(event.is_allday());
+  }
+
   #[test]
   fn test_with_dtend() {
     testdata::setup();
@@ -602,6 +1217,80 @@ mod tests {
   //assert_eq!(timestamp, event.get_dtstart().unwrap());
   //}
 
+  #[test]
+  fn test_get_khevent_at_override() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR_OVERRIDE, None).unwrap();
+
+    let moved = cal.get_khevent_at(Some(&IcalTime::floating_ymd(2018, 10, 19)));
+    assert_eq!(Some("Autocrypt Friday".to_string()), moved.get_summary());
+    assert!(moved.is_recur_override());
+
+    let regular = cal.get_khevent_at(Some(&IcalTime::floating_ymd(2018, 10, 25)));
+    assert_eq!(Some("Autocrypt Thursdays".to_string()), regular.get_summary());
+    assert!(regular.is_recur_instance());
+  }
+
+  #[test]
+  fn test_get_first_event_skips_overrides() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR_OVERRIDE, None).unwrap();
+
+    assert!(cal.get_first_event().get_recurrence_id().is_none());
+    assert_eq!(1, cal.get_override_events().len());
+  }
+
+  #[test]
+  fn test_with_exdate() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR, None).unwrap();
+
+    let new_cal = cal.with_exdate(&IcalTime::floating_ymd(2018, 10, 18)).unwrap();
+
+    assert!(new_cal.to_string().contains("EXDATE;VALUE=DATE:20181018\r\n"));
+  }
+
+  #[test]
+  fn test_with_recurrence_override() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR, None).unwrap();
+    let recurrence_id = IcalTime::floating_ymd(2018, 10, 18);
+
+    let new_cal = cal.with_recurrence_override(&recurrence_id).unwrap();
+    assert_eq!(2, new_cal.events_iter().count());
+    assert!(new_cal.to_string().contains("RECURRENCE-ID;VALUE=DATE:20181018\r\n"));
+
+    let new_cal = new_cal.with_exdate(&recurrence_id).unwrap();
+    assert_eq!(1, new_cal.events_iter().count());
+  }
+
+  #[test]
+  fn test_with_recurrence_end_start() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR, None).unwrap();
+    let recurrence_id = IcalTime::floating_ymd(2018, 10, 25);
+
+    let master = cal.clone().with_recurrence_end(&recurrence_id).unwrap();
+    assert!(master.to_string().contains("RRULE:FREQ=WEEKLY;UNTIL=20181024\r\n"));
+
+    let following = cal.with_recurrence_start(&recurrence_id).unwrap();
+    let content = following.to_string();
+    assert!(content.contains("DTSTART;VALUE=DATE:20181025\r\n"));
+    assert!(content.contains("RRULE:FREQ=WEEKLY;COUNT=8\r\n"));
+  }
+
+  #[test]
+  fn test_with_recurrence_start_exdate_rdate() {
+    let recur = testdata::TEST_EVENT_RECUR.replace(
+      "RRULE:FREQ=WEEKLY;COUNT=10\n",
+      "RRULE:FREQ=WEEKLY;COUNT=10\nEXDATE;VALUE=DATE:20181018\nEXDATE;VALUE=DATE:20181101\nRDATE;VALUE=DATE:20181013\nRDATE;VALUE=DATE:20181014\n",
+    );
+    let cal = IcalVCalendar::from_str(&recur, None).unwrap();
+    let recurrence_id = IcalTime::floating_ymd(2018, 10, 25);
+
+    let following = cal.with_recurrence_start(&recurrence_id).unwrap();
+    let content = following.to_string();
+    assert!(content.contains("RRULE:FREQ=WEEKLY;COUNT=8\r\n"));
+    assert!(content.contains("EXDATE;VALUE=DATE:20181101\r\n"));
+    assert!(!content.contains("EXDATE;VALUE=DATE:20181018"));
+    assert!(!content.contains("RDATE"));
+  }
+
   #[test]
   fn with_uid_test() {
     let path = PathBuf::from("test/path");
@@ -644,6 +1333,70 @@ mod tests {
     }
   }
 
+  #[test]
+  fn get_event_uids_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_IMPORT_MULTIPLE, None).unwrap();
+
+    assert_eq!(vec!["import-berlin", "import-newyork"], cal.get_event_uids().unwrap());
+  }
+
+  #[test]
+  fn with_unused_timezones_removed_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_IMPORT_MULTIPLE, None).unwrap();
+
+    let berlin = cal.clone().with_keep_uid("import-berlin").with_unused_timezones_removed().to_string();
+    assert!(berlin.contains("TZID:Europe/Berlin"));
+    assert!(!berlin.contains("TZID:America/New_York"));
+
+    let newyork = cal.with_keep_uid("import-newyork").with_unused_timezones_removed().to_string();
+    assert!(!newyork.contains("TZID:Europe/Berlin"));
+    assert!(newyork.contains("TZID:America/New_York"));
+  }
+
+  #[test]
+  fn with_components_from_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_IMPORT_MULTIPLE, None).unwrap();
+    let berlin = cal.clone().with_keep_uid("import-berlin").with_unused_timezones_removed();
+    let newyork = cal.with_keep_uid("import-newyork").with_unused_timezones_removed();
+
+    let merged = berlin.clone().with_components_from(&newyork).with_components_from(&berlin);
+    let merged = merged.to_string();
+
+    assert_eq!(2, merged.matches("BEGIN:VTIMEZONE").count());
+    assert_eq!(5, merged.matches("BEGIN:VEVENT").count());
+  }
+
+  #[test]
+  fn with_occurrence_flattened_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR_OVERRIDE, None).unwrap();
+
+    let flattened = cal.clone().with_occurrence_flattened(&IcalTime::floating_ymd(2018, 10, 25)).unwrap();
+    let event = flattened.get_principal_event();
+    assert_eq!(1, flattened.events_iter().count());
+    assert_eq!("autocryptthursday-20181025", event.get_uid());
+    assert_eq!(Some(IcalTime::floating_ymd(2018, 10, 25)), event.get_dtstart());
+    assert!(!event.is_recurring());
+  }
+
+  #[test]
+  fn with_occurrence_flattened_override_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR_OVERRIDE, None).unwrap();
+
+    let flattened = cal.with_occurrence_flattened(&IcalTime::floating_ymd(2018, 10, 18)).unwrap();
+    let event = flattened.get_principal_event();
+    assert_eq!(1, flattened.events_iter().count());
+    assert_eq!(Some("Autocrypt Friday".to_string()), event.get_summary());
+    assert_eq!(Some(IcalTime::floating_ymd(2018, 10, 19)), event.get_dtstart());
+    assert_eq!(None, event.get_recurrence_id());
+  }
+
+  #[test]
+  fn get_sequence_test() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_WITH_TIMEZONE_COMPONENT, None).unwrap();
+
+    assert_eq!(1, cal.get_sequence());
+  }
+
   #[test]
   fn clone_test() {
     let path = PathBuf::from("test/path");
@@ -3,7 +3,9 @@ use std::ffi::CStr;
 use super::IcalComponent;
 use super::IcalDuration;
 use super::IcalTime;
+use super::IcalTimeZone;
 use super::IcalVCalendar;
+use crate::defaults;
 use crate::ical;
 
 pub struct IcalVEvent {
@@ -84,27 +86,75 @@ impl IcalVEvent {
     }
   }
 
+  pub fn has_property_dtend(&self) -> bool {
+    self.get_property(ical::icalproperty_kind_ICAL_DTEND_PROPERTY).is_some()
+  }
+
   pub fn has_property_rrule(&self) -> bool {
     !self
       .get_properties(ical::icalproperty_kind_ICAL_RRULE_PROPERTY)
       .is_empty()
   }
 
+  pub fn has_property_rdate(&self) -> bool {
+    !self
+      .get_properties(ical::icalproperty_kind_ICAL_RDATE_PROPERTY)
+      .is_empty()
+  }
+
+  pub fn get_recurrence_id(&self) -> Option<IcalTime> {
+    unsafe {
+      let recurrence_id = ical::icalcomponent_get_recurrenceid(self.ptr);
+      if ical::icaltime_is_null_time(recurrence_id) == 1 {
+        None
+      } else {
+        Some(IcalTime::from(recurrence_id))
+      }
+    }
+  }
+
+  /// true for events with an RRULE or RDATE, but not for overrides of single
+  /// occurrences
+  pub fn is_recurring(&self) -> bool {
+    (self.has_property_rrule() || self.has_property_rdate()) && self.get_recurrence_id().is_none()
+  }
+
+  /// unrolls the recurrence within the configured horizon around today
   pub fn get_recur_datetimes(&self) -> Vec<IcalTime> {
+    let (future_days, past_days) = defaults::get_recur_horizon();
+    let dtstart = self.get_dtstart().unwrap();
+
+    let (from, until) = unsafe {
+      let mut until = ical::icaltime_today();
+      until.day += future_days as i32;
+      let until = ical::icaltime_normalize(until);
+
+      let mut from = *dtstart;
+      if let Some(past_days) = past_days {
+        let mut limit = ical::icaltime_today();
+        limit.day -= past_days as i32;
+        let limit = ical::icaltime_normalize(limit);
+        if ical::icaltime_compare(limit, from) > 0 {
+          from = limit;
+        }
+      }
+      (IcalTime::from(from), IcalTime::from(until))
+    };
+
+    self.get_recur_datetimes_between(&from, &until)
+  }
+
+  pub fn get_recur_datetimes_between(&self, from: &IcalTime, until: &IcalTime) -> Vec<IcalTime> {
     let mut result: Vec<IcalTime> = vec![];
     let result_ptr: *mut ::std::os::raw::c_void =
       &mut result as *mut _ as *mut ::std::os::raw::c_void;
 
     let dtstart = self.get_dtstart().unwrap();
     unsafe {
-      //unroll up to 1 year in the future
-      let mut dtend = ical::icaltime_today();
-      dtend.year += 1;
-
       ical::icalcomponent_foreach_recurrence(
         self.ptr,
-        *dtstart,
-        dtend,
+        **from,
+        **until,
         Some(recur_callback),
         result_ptr,
       );
@@ -113,10 +163,35 @@ impl IcalVEvent {
     if dtstart.is_date() {
       result = result.into_iter().map(|time| time.as_date()).collect();
     }
+    // an RDATE may repeat DTSTART or an occurrence of the RRULE
+    result.sort_by_key(|time| time.timestamp());
+    result.dedup();
 
     result
   }
 
+  /// Finds the occurrence that starts at `time` and returns it in the same
+  /// form as DTSTART, i.e. as a date or in the same timezone, so that it can
+  /// be used as RECURRENCE-ID or EXDATE.
+  pub fn find_recurrence_id(&self, time: &IcalTime) -> Option<IcalTime> {
+    let dtstart = self.get_dtstart()?;
+    let local = IcalTimeZone::local();
+    let timestamp = time.timestamp();
+    // instances in khlines are either in UTC or, like in the index, in local time
+    let occurrence = self
+      .get_recur_datetimes_between(&dtstart, &time.succ())
+      .into_iter()
+      .find(|recur| recur.timestamp() == timestamp || recur.with_timezone(&local).timestamp() == timestamp)?;
+
+    if dtstart.is_date() {
+      Some(IcalTime::floating_ymd(occurrence.year, occurrence.month, occurrence.day))
+    } else if let Some(timezone) = dtstart.get_timezone() {
+      Some(occurrence.with_timezone(&timezone))
+    } else {
+      Some(occurrence)
+    }
+  }
+
   pub fn shallow_copy(&self) -> IcalVEvent {
     IcalVEvent {
       ptr: self.ptr,
@@ -136,6 +211,21 @@ impl IcalVEvent {
     self.parent.as_ref()
   }
 
+  pub fn get_categories(&self) -> Vec<String> {
+    self
+      .get_properties(ical::icalproperty_kind_ICAL_CATEGORIES_PROPERTY)
+      .iter()
+      .flat_map(|prop| {
+        prop
+          .get_value()
+          .split(',')
+          .map(|category| category.trim().to_string())
+          .collect::<Vec<String>>()
+      })
+      .filter(|category| !category.is_empty())
+      .collect()
+  }
+
   pub fn get_summary(&self) -> Option<String> {
     unsafe {
       let ptr = ical::icalcomponent_get_summary(self.ptr);
@@ -354,6 +444,33 @@ mod tests {
     assert_eq!(Some(IcalDuration::from_seconds(0)), event.get_duration());
   }
 
+  #[test]
+  fn test_get_recur_datetimes_between() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR, None).unwrap();
+    let event = cal.get_principal_event();
+
+    let from = IcalTime::floating_ymd(2018, 10, 22);
+    let until = IcalTime::floating_ymd(2018, 11, 5);
+    let datetimes = event.get_recur_datetimes_between(&from, &until);
+
+    assert_eq!(
+      vec!(IcalTime::floating_ymd(2018, 10, 25), IcalTime::floating_ymd(2018, 11, 1)),
+      datetimes
+    );
+  }
+
+  #[test]
+  fn test_get_recur_datetimes_history() {
+    let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_RECUR, None).unwrap();
+    let event = cal.get_principal_event();
+
+    defaults::set_recur_horizon(defaults::RECUR_HORIZON_DAYS, Some(0));
+    assert!(event.get_recur_datetimes().is_empty());
+
+    defaults::set_recur_horizon(defaults::RECUR_HORIZON_DAYS, None);
+    assert_eq!(10, event.get_recur_datetimes().len());
+  }
+
   #[test]
   fn test_get_description() {
     let cal = IcalVCalendar::from_str(testdata::TEST_EVENT_ONE_MEETING, None).unwrap();
@@ -41,6 +41,23 @@ pub fn default_input_khlines() -> Result<Box<dyn Iterator<Item = KhLine>>, Strin
   Ok(khlines)
 }
 
+/// All lines from stdin, or the cursor if stdin is a terminal.
+pub fn default_input_khlines_or_cursor() -> io::Result<Vec<KhLine>> {
+  if !stdioutils::is_stdin_tty() {
+    debug!("Taking input from Stdin");
+
+    let lines = stdioutils::read_lines_from_stdin()?;
+    let khlines: Vec<KhLine> = lines_to_khlines(lines.into_iter()).collect();
+    if khlines.is_empty() {
+      Err(io::Error::new(io::ErrorKind::Other, "no events in input"))
+    } else {
+      Ok(khlines)
+    }
+  } else {
+    Ok(vec![cursorfile::read_cursorfile()?])
+  }
+}
+
 pub fn default_input_khline() -> io::Result<KhLine> {
   if !stdioutils::is_stdin_tty() {
     debug!("Taking input from Stdin");
@@ -60,6 +77,7 @@ pub fn default_input_khline() -> io::Result<KhLine> {
 mod tests {
   use super::*;
 
+  use crate::testutils::prepare_testdir;
   use crate::utils::stdioutils;
 
   #[test]
@@ -68,4 +86,14 @@ mod tests {
 
     assert!( default_input_khline().is_err());
   }
+
+  #[test]
+  fn test_default_input_khlines_or_cursor() {
+    let _testdir = prepare_testdir("testdir_two_cals");
+    stdioutils::test_stdin_write("first/twodaysacrossbuckets.ics\nsecond/second_sub/twodaysacrossbuckets.ics\n");
+
+    let khlines = default_input_khlines_or_cursor().unwrap();
+
+    assert_eq!(2, khlines.len());
+  }
 }
@@ -0,0 +1,484 @@
+//! A journal of the operations that changed calendar files, so that they can
+//! be undone and redone one at a time.
+//!
+//! Every operation gets its own directory in the backup dir. Before a file is
+//! modified or deleted, it is copied to `<id>/before`. When an operation is
+//! undone, the files it created or modified are copied to `<id>/after` first,
+//! so that it can be redone.
+//!
+//! The journal file has one line per operation, with tab separated fields:
+//! id, action, `done` or `undone`, and one `created:`, `modified:` or
+//! `deleted:` entry per file, relative to the calendar dir. Anything that
+//! reads the journal to write it back holds the journal lock meanwhile.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{Duration, Local, NaiveDateTime};
+
+use crate::defaults;
+use crate::utils::{dateutil, fileutil, lock};
+use crate::KhResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+  Created,
+  Modified,
+  Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+  pub kind: ChangeKind,
+  pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+  pub id: String,
+  pub action: String,
+  pub undone: bool,
+  pub changes: Vec<Change>,
+}
+
+impl Operation {
+  /// Starts a new operation for `action`, with a backup directory of its own.
+  pub fn begin(action: &str) -> io::Result<Self> {
+    let id = new_operation_id();
+    fs::create_dir_all(defaults::get_backupdir().join(&id))?;
+    Ok(Operation { id, action: action.to_string(), undone: false, changes: Vec::new() })
+  }
+
+  pub fn created(&mut self, path: &Path) {
+    self.push_change(ChangeKind::Created, path);
+  }
+
+  /// Takes a backup of `path`, call this before the file is written. A file
+  /// is only backed up the first time the operation touches it.
+  pub fn modified(&mut self, path: &Path) -> io::Result<()> {
+    if !self.touches(path) {
+      self.backup(path)?;
+    }
+    self.push_change(ChangeKind::Modified, path);
+    Ok(())
+  }
+
+  /// Takes a backup of `path`, call this before the file is removed.
+  pub fn deleted(&mut self, path: &Path) -> io::Result<()> {
+    if !self.touches(path) {
+      self.backup(path)?;
+    }
+    self.push_change(ChangeKind::Deleted, path);
+    Ok(())
+  }
+
+  fn touches(&self, path: &Path) -> bool {
+    let path = relative_to_caldir(path);
+    self.changes.iter().any(|change| change.path == path)
+  }
+
+  /// Appends the operation to the journal. Operations that were undone before
+  /// can no longer be redone after this, so they are dropped along with their
+  /// backups.
+  pub fn commit(self) -> KhResult<()> {
+    if self.changes.is_empty() {
+      fs::remove_dir_all(self.get_backup_dir())?;
+      return Ok(());
+    }
+    {
+      let _lock = lock::lock_file_exclusive(&defaults::get_journallockfile())?;
+      let (undone, mut operations): (Vec<Operation>, Vec<Operation>) =
+        read_journal()?.into_iter().partition(|operation| operation.undone);
+      operations.push(self);
+      write_journal(&operations)?;
+
+      for operation in &undone {
+        let backup_dir = operation.get_backup_dir();
+        if backup_dir.exists() {
+          fs::remove_dir_all(backup_dir)?;
+          info!("Removed backup {}", operation.id);
+        }
+      }
+    }
+
+    let (keep_days, keep_operations) = defaults::get_backup_retention();
+    if keep_days.is_some() || keep_operations.is_some() {
+      prune(keep_days, keep_operations)?;
+    }
+    Ok(())
+  }
+
+  pub fn get_backup_dir(&self) -> PathBuf {
+    defaults::get_backupdir().join(&self.id)
+  }
+
+  /// Only files in the calendar dir can be backed up, anything else would end
+  /// up outside of the backup dir.
+  fn backup(&self, path: &Path) -> io::Result<()> {
+    let relative_path = path.strip_prefix(defaults::get_caldir()).map_err(|_| {
+      io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not in the calendar dir", path.display()))
+    })?;
+    let target = self.get_backup_dir().join("before").join(relative_path);
+    copy_file(path, &target)
+  }
+
+  fn push_change(&mut self, kind: ChangeKind, path: &Path) {
+    let path = relative_to_caldir(path).to_path_buf();
+    if !self.changes.iter().any(|change| change.path == path) {
+      self.changes.push(Change { kind, path });
+    }
+  }
+
+  /// Reverts the changes of this operation, returns the paths it touched.
+  pub fn undo(&mut self) -> KhResult<Vec<PathBuf>> {
+    let caldir = defaults::get_caldir();
+    let backup_dir = self.get_backup_dir();
+    let mut paths = Vec::new();
+    for change in self.changes.iter().rev() {
+      let path = caldir.join(&change.path);
+      let before = backup_dir.join("before").join(&change.path);
+      let after = backup_dir.join("after").join(&change.path);
+      match change.kind {
+        ChangeKind::Created => {
+          if path.exists() {
+            copy_file(&path, &after)?;
+            fs::remove_file(&path)?;
+          }
+        }
+        ChangeKind::Modified => {
+          copy_file(&path, &after)?;
+          copy_file(&before, &path)?;
+        }
+        ChangeKind::Deleted => copy_file(&before, &path)?,
+      }
+      paths.push(path);
+    }
+    self.undone = true;
+    Ok(paths)
+  }
+
+  /// Applies the changes of this operation again, returns the paths it touched.
+  pub fn redo(&mut self) -> KhResult<Vec<PathBuf>> {
+    let caldir = defaults::get_caldir();
+    let backup_dir = self.get_backup_dir();
+    let mut paths = Vec::new();
+    for change in &self.changes {
+      let path = caldir.join(&change.path);
+      let after = backup_dir.join("after").join(&change.path);
+      match change.kind {
+        ChangeKind::Created | ChangeKind::Modified => copy_file(&after, &path)?,
+        ChangeKind::Deleted => {
+          if path.exists() {
+            fs::remove_file(&path)?;
+          }
+        }
+      }
+      paths.push(path);
+    }
+    self.undone = false;
+    Ok(paths)
+  }
+}
+
+impl fmt::Display for ChangeKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      ChangeKind::Created => "created",
+      ChangeKind::Modified => "modified",
+      ChangeKind::Deleted => "deleted",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+impl FromStr for ChangeKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "created" => Ok(ChangeKind::Created),
+      "modified" => Ok(ChangeKind::Modified),
+      "deleted" => Ok(ChangeKind::Deleted),
+      _ => Err(format!("unknown change {}", s)),
+    }
+  }
+}
+
+impl fmt::Display for Operation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let state = if self.undone { "undone" } else { "done" };
+    write!(f, "{}\t{}\t{}", self.id, self.action, state)?;
+    for change in &self.changes {
+      write!(f, "\t{}:{}", change.kind, change.path.display())?;
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Operation {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut fields = s.split('\t');
+    let id = fields.next().filter(|id| !id.is_empty()).ok_or("missing operation id")?;
+    let action = fields.next().ok_or("missing action")?;
+    let undone = match fields.next() {
+      Some("done") => false,
+      Some("undone") => true,
+      _ => return Err(format!("invalid state in journal entry {}", id)),
+    };
+    let changes = fields
+      .map(|field| {
+        let mut parts = field.splitn(2, ':');
+        let kind = parts.next().unwrap_or_default().parse::<ChangeKind>()?;
+        let path = parts.next().ok_or_else(|| format!("missing path in {}", field))?;
+        Ok(Change { kind, path: PathBuf::from(path) })
+      })
+      .collect::<Result<Vec<Change>, String>>()?;
+
+    Ok(Operation { id: id.to_string(), action: action.to_string(), undone, changes })
+  }
+}
+
+pub fn read_journal() -> KhResult<Vec<Operation>> {
+  let journalfile = defaults::get_journalfile();
+  if !journalfile.exists() {
+    return Ok(Vec::new());
+  }
+  let operations = fileutil::read_lines_from_file(&journalfile)?
+    .filter(|line| !line.is_empty())
+    .map(|line| line.parse::<Operation>())
+    .collect::<Result<Vec<Operation>, String>>()?;
+  Ok(operations)
+}
+
+pub fn write_journal(operations: &[Operation]) -> KhResult<()> {
+  let tmpfilename = defaults::get_datafile("tmpjournal");
+  let mut content = String::new();
+  for operation in operations {
+    content.push_str(&operation.to_string());
+    content.push('\n');
+  }
+  fileutil::write_file(&tmpfilename, &content)?;
+  fs::rename(tmpfilename, defaults::get_journalfile())?;
+  Ok(())
+}
+
+/// Removes the backups that are older than `keep_days`, or that are not among
+/// the newest `keep_operations`, along with their journal entries. Backup
+/// directories that are not named after an operation are left alone.
+/// Returns the ids of the removed backups.
+pub fn prune(keep_days: Option<u32>, keep_operations: Option<usize>) -> KhResult<Vec<String>> {
+  let backupdir = defaults::get_backupdir();
+  if !backupdir.exists() {
+    return Ok(Vec::new());
+  }
+  let _lock = lock::lock_file_exclusive(&defaults::get_journallockfile())?;
+
+  let mut backups: Vec<(NaiveDateTime, u32, String)> = backupdir
+    .read_dir()?
+    .filter_map(|result| result.ok())
+    .filter(|dir_entry| dir_entry.path().is_dir())
+    .map(|dir_entry| dir_entry.file_name().to_string_lossy().into_owned())
+    .filter_map(|id| parse_operation_id(&id).map(|(time, counter)| (time, counter, id)))
+    .collect();
+  backups.sort_unstable();
+
+  let now = dateutil::now().with_timezone(&Local).naive_local();
+  let oldest = keep_days.map(|days| now - Duration::days(i64::from(days)));
+  let excess = keep_operations.map_or(0, |keep| backups.len().saturating_sub(keep));
+
+  let pruned: Vec<String> = backups
+    .into_iter()
+    .enumerate()
+    .filter(|(index, (time, _, _))| *index < excess || oldest.map_or(false, |oldest| *time < oldest))
+    .map(|(_, (_, _, id))| id)
+    .collect();
+
+  for id in &pruned {
+    fs::remove_dir_all(backupdir.join(id))?;
+    info!("Removed backup {}", id);
+  }
+
+  if !pruned.is_empty() && defaults::get_journalfile().exists() {
+    let mut operations = read_journal()?;
+    operations.retain(|operation| !pruned.contains(&operation.id));
+    write_journal(&operations)?;
+  }
+
+  Ok(pruned)
+}
+
+/// Splits an operation id into its timestamp and counter.
+pub fn parse_operation_id(id: &str) -> Option<(NaiveDateTime, u32)> {
+  let timestamp = id.get(..19)?;
+  let time = NaiveDateTime::parse_from_str(timestamp, "%FT%T").ok()?;
+  let counter = match &id[19..] {
+    "" => 0,
+    suffix if suffix.starts_with('-') => suffix[1..].parse::<u32>().ok()?,
+    _ => return None,
+  };
+  Some((time, counter))
+}
+
+/// A timestamp, with a counter appended if there already is a backup
+/// directory of that name.
+fn new_operation_id() -> String {
+  let backupdir = defaults::get_backupdir();
+  let timestamp = dateutil::now().with_timezone(&Local).format("%FT%T").to_string();
+  let mut id = timestamp.clone();
+  let mut counter = 1;
+  while backupdir.join(&id).exists() {
+    id = format!("{}-{}", timestamp, counter);
+    counter += 1;
+  }
+  id
+}
+
+fn relative_to_caldir(path: &Path) -> &Path {
+  path.strip_prefix(defaults::get_caldir()).unwrap_or(path)
+}
+
+fn copy_file(from: &Path, to: &Path) -> io::Result<()> {
+  if let Some(parent) = to.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  fs::copy(from, to)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::testutils::prepare_testdir;
+  use assert_fs::prelude::*;
+  use predicates::prelude::*;
+
+  #[test]
+  fn test_operation_roundtrip() {
+    let line = "2013-01-01T02:02:03\tmodify\tdone\tmodified:my_calendar/a.ics\tcreated:b.ics";
+    let operation = line.parse::<Operation>().unwrap();
+
+    assert_eq!("modify", operation.action);
+    assert!(!operation.undone);
+    assert_eq!(Change { kind: ChangeKind::Modified, path: PathBuf::from("my_calendar/a.ics") }, operation.changes[0]);
+    assert_eq!(line, operation.to_string());
+  }
+
+  #[test]
+  fn test_operation_parse_negative() {
+    assert!("".parse::<Operation>().is_err());
+    assert!("id\tmodify\tmaybe".parse::<Operation>().is_err());
+    assert!("id\tmodify\tdone\trenamed:a.ics".parse::<Operation>().is_err());
+  }
+
+  #[test]
+  fn test_parse_operation_id() {
+    let time = NaiveDateTime::parse_from_str("2013-01-01T02:02:03", "%FT%T").unwrap();
+
+    assert_eq!(Some((time, 0)), parse_operation_id("2013-01-01T02:02:03"));
+    assert_eq!(Some((time, 12)), parse_operation_id("2013-01-01T02:02:03-12"));
+    assert_eq!(None, parse_operation_id("2013-01-01T02:02:03x"));
+    assert_eq!(None, parse_operation_id("backup_id"));
+  }
+
+  #[test]
+  fn test_operation_ids_unique() {
+    let _testdir = prepare_testdir("testdir");
+
+    let first = Operation::begin("edit").unwrap();
+    let second = Operation::begin("edit").unwrap();
+
+    assert_ne!(first.id, second.id);
+  }
+
+  #[test]
+  fn test_undo_redo_modified() {
+    let testdir = prepare_testdir("testdir");
+    let file = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+    let original = fileutil::read_file_to_string(file.path()).unwrap();
+
+    let mut operation = Operation::begin("edit").unwrap();
+    operation.modified(file.path()).unwrap();
+    file.write_str("changed").unwrap();
+    operation.commit().unwrap();
+
+    let mut operation = read_journal().unwrap().pop().unwrap();
+    operation.undo().unwrap();
+    file.assert(original.as_str());
+
+    operation.redo().unwrap();
+    file.assert("changed");
+  }
+
+  #[test]
+  fn test_modified_twice_keeps_first_backup() {
+    let testdir = prepare_testdir("testdir");
+    let file = testdir.child(".khaleesi/cal/twodaysacrossbuckets.ics");
+    let original = fileutil::read_file_to_string(file.path()).unwrap();
+
+    let mut operation = Operation::begin("edit").unwrap();
+    operation.modified(file.path()).unwrap();
+    file.write_str("first").unwrap();
+    operation.modified(file.path()).unwrap();
+    file.write_str("second").unwrap();
+    operation.commit().unwrap();
+
+    let mut operation = read_journal().unwrap().pop().unwrap();
+    assert_eq!(1, operation.changes.len());
+    operation.undo().unwrap();
+    file.assert(original.as_str());
+  }
+
+  #[test]
+  fn test_backup_outside_caldir() {
+    let testdir = prepare_testdir("testdir");
+    let file = testdir.child("outside.ics");
+    file.write_str("outside").unwrap();
+
+    let mut operation = Operation::begin("edit").unwrap();
+    assert!(file.path().is_absolute());
+    assert!(operation.modified(file.path()).is_err());
+    assert!(operation.deleted(file.path()).is_err());
+
+    file.assert("outside");
+    assert!(operation.changes.is_empty());
+  }
+
+  #[test]
+  fn test_commit_locked_journal() {
+    let testdir = prepare_testdir("testdir");
+    let _lock = lock::lock_file_exclusive(&defaults::get_journallockfile()).unwrap();
+
+    let mut operation = Operation::begin("new").unwrap();
+    operation.created(testdir.child(".khaleesi/cal/new.ics").path());
+    assert!(operation.commit().is_err());
+  }
+
+  #[test]
+  fn test_commit_drops_undone() {
+    let testdir = prepare_testdir("testdir");
+    let file = testdir.child(".khaleesi/cal/new.ics");
+
+    let mut operation = Operation::begin("new").unwrap();
+    operation.created(file.path());
+    operation.undone = true;
+    let undone_backup_dir = operation.get_backup_dir();
+    operation.commit().unwrap();
+    assert!(undone_backup_dir.exists());
+
+    let mut operation = Operation::begin("copy").unwrap();
+    operation.created(file.path());
+    operation.commit().unwrap();
+
+    let operations = read_journal().unwrap();
+    assert_eq!(1, operations.len());
+    assert_eq!("copy", operations[0].action);
+    testdir.child(".khaleesi/journal").assert(predicate::str::contains("created:new.ics"));
+    assert!(!undone_backup_dir.exists());
+  }
+}
@@ -51,6 +51,11 @@ impl KhEvent {
     self.event.get_parent()?.get_path()
   }
 
+  /// The timezone of DTSTART, also for instances of recurring events.
+  pub fn get_start_timezone(&self) -> Option<IcalTimeZone> {
+    self.event.get_dtstart()?.get_timezone()
+  }
+
   pub fn is_allday(&self) -> bool {
     self.event.is_allday()
   }
@@ -71,6 +76,10 @@ impl KhEvent {
     self.event.get_location()
   }
 
+  pub fn get_categories(&self) -> Vec<String> {
+    self.event.get_categories()
+  }
+
   pub fn get_uid(&self) -> String {
     self.event.get_uid()
   }
@@ -102,11 +111,16 @@ impl KhEvent {
   }
 
   pub fn is_recur_master(&self) -> bool {
-    self.event.has_property_rrule() && self.instance_timestamp.is_none()
+    self.event.is_recurring() && self.instance_timestamp.is_none()
   }
 
   pub fn is_recur_instance(&self) -> bool {
-    self.event.has_property_rrule() && self.instance_timestamp.is_some()
+    self.event.is_recurring() && self.instance_timestamp.is_some()
+  }
+
+  /// true for events that override a single occurrence of a recurring event
+  pub fn is_recur_override(&self) -> bool {
+    self.event.get_recurrence_id().is_some()
   }
 
 
@@ -121,6 +135,19 @@ impl KhEvent {
     }
   }
 
+  /// The RECURRENCE-ID of this occurrence, if it is one.
+  pub fn get_recurrence_id(&self) -> Option<IcalTime> {
+    if self.is_recur_override() {
+      return self.event.get_recurrence_id();
+    }
+    if !self.is_recur_instance() {
+      return None;
+    }
+    self.event.find_recurrence_id(self.instance_timestamp.as_ref()?)
+  }
+
+  /// Occurrences that are overridden by another VEVENT in the same file are
+  /// left out, the overrides are indexed on their own.
   pub fn get_recur_instances(&self) -> impl Iterator<Item = KhEvent> + '_ {
     self
       .get_recur_datetimes()
@@ -129,8 +156,33 @@ impl KhEvent {
       .map(move |recur_local| self.with_internal_timestamp(&recur_local))
   }
 
+  pub fn get_recur_instances_between(&self, from: &IcalTime, until: &IcalTime) -> impl Iterator<Item = KhEvent> + '_ {
+    let datetimes = self.event.get_recur_datetimes_between(from, until);
+    self
+      .without_overridden(datetimes)
+      .into_iter()
+      .map(|recur_utc| recur_utc.with_timezone(&IcalTimeZone::local()))
+      .map(move |recur_local| self.with_internal_timestamp(&recur_local))
+  }
+
   pub fn get_recur_datetimes(&self) -> Vec<IcalTime> {
-    self.event.get_recur_datetimes()
+    self.without_overridden(self.event.get_recur_datetimes())
+  }
+
+  fn without_overridden(&self, datetimes: Vec<IcalTime>) -> Vec<IcalTime> {
+    let overridden: Vec<i64> = match self.event.get_parent() {
+      Some(cal) => cal
+        .get_override_events()
+        .iter()
+        .filter_map(|event| event.get_recurrence_id())
+        .map(|recurrence_id| recurrence_id.timestamp())
+        .collect(),
+      None => return datetimes,
+    };
+    datetimes
+      .into_iter()
+      .filter(|datetime| !overridden.contains(&datetime.timestamp()))
+      .collect()
   }
 
   pub fn get_properties_by_name(&self, property_name: &str) -> Vec<IcalProperty> {
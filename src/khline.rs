@@ -33,8 +33,7 @@ impl KhLine {
 
   pub fn to_event(&self) -> io::Result<KhEvent> {
     let calendar = self.to_cal()?;
-    let event = calendar.get_first_event();
-    Ok(KhEvent::from_event_with_timestamp(event, self.time.clone()))
+    Ok(calendar.get_khevent_at(self.time.as_ref()))
   }
 
   pub fn with_index(self, index: usize) -> Self {
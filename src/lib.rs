@@ -5,7 +5,6 @@ pub mod errors;
 pub type KhResult<T> = Result<T,errors::KhError>;
 
 pub mod actions;
-pub mod backup;
 pub mod calendars;
 pub mod cli;
 pub mod config;
@@ -14,8 +13,10 @@ pub mod defaults;
 pub mod edit;
 pub mod icalwrap;
 pub mod input;
+pub mod journal;
 pub mod khevent;
 pub mod khline;
+pub mod output;
 pub mod selectors;
 pub mod seqfile;
 pub mod utils;
@@ -0,0 +1,158 @@
+//! Machine readable output of events, for scripts building on khaleesi.
+
+use crate::defaults;
+use crate::icalwrap::IcalTime;
+use crate::khevent::KhEvent;
+use crate::KhResult;
+use structopt::StructOpt;
+
+arg_enum! {
+#[derive(Debug, Clone, Copy, PartialEq)]
+  pub enum OutputFormat {
+    Text,
+    Json,
+    Jsonl,
+  }
+}
+
+impl Default for OutputFormat {
+  fn default() -> Self {
+    OutputFormat::Text
+  }
+}
+
+/// The `--format` option of the commands that print events.
+#[derive(Debug, Default, StructOpt)]
+pub struct OutputArgs {
+  /// print the events as text, json or jsonl
+  #[structopt(long = "format", raw(possible_values = "&OutputFormat::variants()", case_insensitive = "true"), default_value = "text")]
+  pub format: OutputFormat,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EventRecord {
+  pub path: Option<String>,
+  pub timestamp: Option<i64>,
+  pub uid: String,
+  pub calendar: Option<String>,
+  pub start: Option<TimeRecord>,
+  pub end: Option<TimeRecord>,
+  pub allday: bool,
+  pub summary: Option<String>,
+  pub location: Option<String>,
+  pub description: Option<String>,
+  pub categories: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ical: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct TimeRecord {
+  pub timestamp: i64,
+  pub datetime: String,
+  pub timezone: Option<String>,
+}
+
+impl From<&KhEvent> for EventRecord {
+  fn from(event: &KhEvent) -> Self {
+    let caldir = defaults::get_caldir();
+    let path = event
+      .get_path()
+      .map(|path| path.strip_prefix(&caldir).unwrap_or(path).to_string_lossy().into_owned());
+    EventRecord {
+      path,
+      timestamp: event.get_start().map(|start| start.timestamp()),
+      uid: event.get_uid(),
+      calendar: event.get_calendar_name(),
+      start: event.get_start().as_ref().map(TimeRecord::from),
+      end: event.get_end().as_ref().map(TimeRecord::from),
+      allday: event.is_allday(),
+      summary: event.get_summary(),
+      location: event.get_location(),
+      description: event.get_description(),
+      categories: event.get_categories(),
+      ical: None,
+    }
+  }
+}
+
+impl From<&IcalTime> for TimeRecord {
+  fn from(time: &IcalTime) -> Self {
+    TimeRecord {
+      timestamp: time.timestamp(),
+      datetime: time.to_iso8601(),
+      timezone: time.get_timezone().map(|timezone| timezone.get_tzid()),
+    }
+  }
+}
+
+/// Prints the records as one JSON array, or as JSON Lines with one record
+/// per line. Does nothing for the text format, which every action prints in
+/// its own way.
+pub fn print_records(format: OutputFormat, records: &[EventRecord]) -> KhResult<()> {
+  match format {
+    OutputFormat::Text => (),
+    OutputFormat::Json => khprintln!("{}", serde_json::to_string_pretty(records)?),
+    OutputFormat::Jsonl => {
+      for record in records {
+        khprintln!("{}", serde_json::to_string(record)?);
+      }
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::testdata;
+  use crate::utils::stdioutils;
+
+  #[test]
+  fn test_event_record() {
+    testdata::setup();
+    let event = KhEvent::from_str(testdata::TEST_EVENT_WITH_TIMEZONE_COMPONENT, None).unwrap();
+
+    let record = EventRecord::from(&event);
+
+    assert_eq!("O2G1SKNFDGC1OZ1675I1A9OFQOFZXTNONYNO", record.uid);
+    assert_eq!(Some("Some Event".to_string()), record.summary);
+    assert_eq!(Some("Some Location".to_string()), record.location);
+    assert!(!record.allday);
+    let start = record.start.unwrap();
+    assert_eq!("2018-10-26T13:30:00+02:00", start.datetime);
+    assert_eq!(Some("Europe/Berlin".to_string()), start.timezone);
+    assert_eq!("2018-10-26T16:00:00+02:00", record.end.unwrap().datetime);
+  }
+
+  #[test]
+  fn test_print_records_jsonl() {
+    let records = vec![
+      EventRecord {
+        path: Some("cal.ics".to_string()),
+        timestamp: None,
+        uid: "uid1".to_string(),
+        calendar: None,
+        start: Some(TimeRecord { timestamp: 1183032000, datetime: "2007-06-28".to_string(), timezone: None }),
+        end: None,
+        allday: true,
+        summary: Some("First Event".to_string()),
+        location: None,
+        description: None,
+        categories: vec!["music".to_string()],
+        ical: None,
+      },
+    ];
+
+    print_records(OutputFormat::Jsonl, &records).unwrap();
+
+    let expected = concat!(
+      r#"{"path":"cal.ics","timestamp":null,"uid":"uid1","calendar":null,"#,
+      r#""start":{"timestamp":1183032000,"datetime":"2007-06-28","timezone":null},"end":null,"#,
+      r#""allday":true,"summary":"First Event","location":null,"description":null,"categories":["music"]}"#,
+      "\n"
+    );
+    assert_eq!(expected, stdioutils::test_stdout_clear());
+  }
+}
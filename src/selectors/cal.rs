@@ -8,9 +8,10 @@ pub struct CalendarFilter {
 }
 
 impl SelectFilter for CalendarFilter {
-  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) {
-    let term = it.next().unwrap();
+  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) -> Result<(), String> {
+    let term = it.next().ok_or("cal needs a calendar name")?;
     self.cal_names.push(term.to_lowercase());
+    Ok(())
   }
 
   fn is_not_empty(&self) -> bool {
@@ -63,13 +63,8 @@ impl FromStr for SelectFilterFrom {
   type Err = String;
 
   fn from_str(s: &str) -> Result<SelectFilterFrom, Self::Err> {
-    if let Ok(date) = dateutil::date_from_str(s) {
-      return Ok(SelectFilterFrom::from_date(Some(date)));
-    }
-    if let Ok(weekdate) = dateutil::week_from_str_begin(s) {
-      return Ok(SelectFilterFrom::from_date(Some(weekdate)));
-    }
-    Err(format!("Could not parse date '{}'", s).to_string())
+    let (begin, _) = dateutil::daterange_from_str(s)?;
+    Ok(SelectFilterFrom::from_date(Some(begin)))
   }
 }
 
@@ -77,13 +72,8 @@ impl FromStr for SelectFilterTo {
   type Err = String;
 
   fn from_str(s: &str) -> Result<SelectFilterTo, Self::Err> {
-    if let Ok(date) = dateutil::date_from_str(s) {
-      return Ok(SelectFilterTo::from_date(Some(date)));
-    }
-    if let Ok(weekdate) = dateutil::week_from_str_end(s) {
-      return Ok(SelectFilterTo::from_date(Some(weekdate)));
-    }
-    Err(format!("Could not parse date '{}'", s).to_string())
+    let (_, end) = dateutil::daterange_from_str(s)?;
+    Ok(SelectFilterTo::from_date(Some(end)))
   }
 }
 
@@ -177,6 +167,27 @@ mod tests {
     assert_eq!(true, filtered);
   }
   #[test]
+  fn test_in_month() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["in", "2007-07"]);
+    assert_eq!(true, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["in", "2007-08"]);
+    assert_eq!(false, filtered);
+  }
+  #[test]
+  fn test_on_year() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["on", "2007"]);
+    assert_eq!(true, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["on", "2008"]);
+    assert_eq!(false, filtered);
+  }
+  #[test]
+  fn test_from_relative_two_terms() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["to", "next", "week"]);
+    assert_eq!(true, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["from", "last", "friday"]);
+    assert_eq!(false, filtered);
+  }
+  #[test]
   fn test_to_begins_redundant() {
     let filtered = test_filter_event(
       &testdata::TEST_EVENT_MULTIDAY,
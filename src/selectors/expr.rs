@@ -0,0 +1,185 @@
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use super::SelectFilter;
+use crate::khevent::KhEvent;
+
+/// A boolean combination of `grep`, `cal` and `prop` filters.
+///
+/// Precedence, from strongest to weakest binding:
+///
+/// 1. parentheses: `( ... )`
+/// 2. `not`
+/// 3. `and`
+/// 4. `or`
+///
+/// Filters that follow each other without an operator are joined by `or`,
+/// so `cal work grep standup` is the same as `cal work or grep standup`.
+pub enum FilterExpr {
+  Filter(Box<dyn SelectFilter>),
+  Not(Box<FilterExpr>),
+  And(Vec<FilterExpr>),
+  Or(Vec<FilterExpr>),
+}
+
+pub enum ExprToken {
+  And,
+  Or,
+  Not,
+  Open,
+  Close,
+  Filter(Box<dyn SelectFilter>),
+}
+
+impl FilterExpr {
+  pub fn includes(&self, event: &KhEvent) -> bool {
+    match self {
+      FilterExpr::Filter(filter) => filter.includes(event),
+      FilterExpr::Not(expr) => !expr.includes(event),
+      FilterExpr::And(exprs) => exprs.iter().all(|expr| expr.includes(event)),
+      FilterExpr::Or(exprs) => exprs.iter().any(|expr| expr.includes(event)),
+    }
+  }
+
+  pub fn parse(tokens: Vec<ExprToken>) -> Result<Option<Self>, String> {
+    if tokens.is_empty() {
+      return Ok(None);
+    }
+    let mut parser = ExprParser { tokens: tokens.into_iter().peekable() };
+    let expr = parser.parse_or()?;
+    if parser.tokens.next().is_some() {
+      return Err("Unbalanced parentheses in selector".to_string());
+    }
+    Ok(Some(expr))
+  }
+}
+
+struct ExprParser {
+  tokens: Peekable<IntoIter<ExprToken>>,
+}
+
+impl ExprParser {
+  fn parse_or(&mut self) -> Result<FilterExpr, String> {
+    let mut exprs = vec![self.parse_and()?];
+    loop {
+      match self.tokens.peek() {
+        Some(ExprToken::Close) | None => break,
+        Some(ExprToken::Or) => {
+          self.tokens.next();
+          exprs.push(self.parse_and()?);
+        }
+        Some(_) => exprs.push(self.parse_and()?),
+      }
+    }
+    Ok(Self::flatten(exprs, FilterExpr::Or))
+  }
+
+  fn parse_and(&mut self) -> Result<FilterExpr, String> {
+    let mut exprs = vec![self.parse_not()?];
+    while let Some(ExprToken::And) = self.tokens.peek() {
+      self.tokens.next();
+      exprs.push(self.parse_not()?);
+    }
+    Ok(Self::flatten(exprs, FilterExpr::And))
+  }
+
+  fn parse_not(&mut self) -> Result<FilterExpr, String> {
+    if let Some(ExprToken::Not) = self.tokens.peek() {
+      self.tokens.next();
+      let expr = self.parse_not()?;
+      return Ok(FilterExpr::Not(Box::new(expr)));
+    }
+    self.parse_primary()
+  }
+
+  fn parse_primary(&mut self) -> Result<FilterExpr, String> {
+    match self.tokens.next() {
+      Some(ExprToken::Filter(filter)) => Ok(FilterExpr::Filter(filter)),
+      Some(ExprToken::Open) => {
+        let expr = self.parse_or()?;
+        match self.tokens.next() {
+          Some(ExprToken::Close) => Ok(expr),
+          _ => Err("Missing closing parenthesis in selector".to_string()),
+        }
+      }
+      Some(ExprToken::Close) => Err("Unexpected ')' in selector".to_string()),
+      Some(ExprToken::And) => Err("Unexpected 'and' in selector".to_string()),
+      Some(ExprToken::Or) => Err("Unexpected 'or' in selector".to_string()),
+      Some(ExprToken::Not) | None => Err("Incomplete selector expression".to_string()),
+    }
+  }
+
+  fn flatten(mut exprs: Vec<FilterExpr>, combine: fn(Vec<FilterExpr>) -> FilterExpr) -> FilterExpr {
+    if exprs.len() == 1 {
+      exprs.pop().unwrap()
+    } else {
+      combine(exprs)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test::test_filter_event;
+  use super::super::SelectFilters;
+  use crate::testdata;
+  use std::path::PathBuf;
+
+  fn filter_cal1(args: &[&str]) -> bool {
+    let path = PathBuf::from("test/cal1/event1.ics");
+    test_filter_event(&testdata::TEST_EVENT_MULTIDAY, Some(&path), args)
+  }
+
+  #[test]
+  fn test_and() {
+    assert!(filter_cal1(&["cal", "cal1", "and", "grep", "Jazz"]));
+    assert!(!filter_cal1(&["cal", "cal1", "and", "grep", "nonexistent"]));
+  }
+
+  #[test]
+  fn test_or() {
+    assert!(filter_cal1(&["cal", "cal2", "or", "grep", "Jazz"]));
+    assert!(!filter_cal1(&["cal", "cal2", "or", "grep", "nonexistent"]));
+  }
+
+  #[test]
+  fn test_implicit_or() {
+    assert!(filter_cal1(&["cal", "cal2", "grep", "Jazz"]));
+  }
+
+  #[test]
+  fn test_not() {
+    assert!(!filter_cal1(&["not", "cal", "cal1"]));
+    assert!(filter_cal1(&["not", "not", "cal", "cal1"]));
+    assert!(!filter_cal1(&["cal", "cal1", "and", "not", "grep", "Jazz"]));
+  }
+
+  #[test]
+  fn test_precedence() {
+    // nonexistent or (Jazz and cal2)
+    assert!(!filter_cal1(&["grep", "nonexistent", "or", "grep", "Jazz", "and", "cal", "cal2"]));
+    // (not cal2) and Jazz
+    assert!(filter_cal1(&["not", "cal", "cal2", "and", "grep", "Jazz"]));
+  }
+
+  #[test]
+  fn test_parentheses() {
+    assert!(filter_cal1(&["(", "grep", "nonexistent", "or", "grep", "Jazz", ")", "and", "cal", "cal1"]));
+    assert!(!filter_cal1(&["not", "(", "cal", "cal1", "and", "grep", "Jazz", ")"]));
+  }
+
+  #[test]
+  fn test_with_daterange() {
+    assert!(filter_cal1(&["from", "2007-06-01", "cal", "cal1", "and", "grep", "Jazz"]));
+    assert!(!filter_cal1(&["from", "2007-08-01", "cal", "cal1", "and", "grep", "Jazz"]));
+  }
+
+  #[test]
+  fn test_parse_negative() {
+    assert!(SelectFilters::parse_from_args(&["(", "grep", "Jazz"]).is_err());
+    assert!(SelectFilters::parse_from_args(&["grep", "Jazz", ")"]).is_err());
+    assert!(SelectFilters::parse_from_args(&["and", "grep", "Jazz"]).is_err());
+    assert!(SelectFilters::parse_from_args(&["grep", "Jazz", "and"]).is_err());
+    assert!(SelectFilters::parse_from_args(&["(", ")"]).is_err());
+  }
+}
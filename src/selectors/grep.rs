@@ -1,16 +1,62 @@
 use super::*;
 
 //use crate::icalwrap::IcalVEvent;
+use super::matcher::TermMatcher;
 use crate::khevent::KhEvent;
 
+/// Properties that are searched if no others were given.
+pub static GREP_DEFAULT_PROPERTIES: [&str; 3] = ["SUMMARY", "DESCRIPTION", "LOCATION"];
+
 pub struct GrepFilter {
-  terms: Vec<String>,
+  terms: Vec<TermMatcher>,
+  properties: Vec<String>,
+}
+
+/// A single term found in a property of an event. `start` and `end` are byte
+/// offsets into `value`.
+#[derive(Debug, PartialEq)]
+pub struct GrepMatch {
+  pub property: String,
+  pub value: String,
+  pub start: usize,
+  pub end: usize,
+}
+
+impl GrepFilter {
+  pub fn new(terms: &[String], properties: &[String]) -> Result<Self, String> {
+    let properties = if properties.is_empty() {
+      GREP_DEFAULT_PROPERTIES.iter().map(|prop| prop.to_string()).collect()
+    } else {
+      properties.iter().map(|prop| prop.to_uppercase()).collect()
+    };
+    let terms = terms
+      .iter()
+      .map(|term| term.parse::<TermMatcher>())
+      .collect::<Result<Vec<TermMatcher>, String>>()?;
+    Ok(GrepFilter { terms, properties })
+  }
+
+  pub fn find_matches(&self, event: &KhEvent) -> Vec<GrepMatch> {
+    let mut matches = Vec::new();
+    for property in &self.properties {
+      for prop in event.get_properties_by_name(property) {
+        let value = prop.get_value();
+        for term in &self.terms {
+          if let Some((start, end)) = term.find(&value) {
+            matches.push(GrepMatch { property: property.clone(), value: value.clone(), start, end });
+          }
+        }
+      }
+    }
+    matches
+  }
 }
 
 impl SelectFilter for GrepFilter {
-  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) {
-    let term = it.next().unwrap();
-    self.terms.push(term.to_lowercase());
+  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) -> Result<(), String> {
+    let term = it.next().ok_or("grep needs a search term")?;
+    self.terms.push(term.parse()?);
+    Ok(())
   }
 
   fn is_not_empty(&self) -> bool {
@@ -18,35 +64,22 @@ impl SelectFilter for GrepFilter {
   }
 
   fn includes(&self, event: &KhEvent) -> bool {
-    for term in &self.terms {
-      if let Some(summary) = event.get_summary() {
-        if summary.to_lowercase().contains(term) {
-          return true;
-        }
-      }
-      if let Some(description) = event.get_description() {
-        if description.to_lowercase().contains(term) {
-          return true;
-        }
-      }
-      if let Some(location) = event.get_location() {
-        if location.to_lowercase().contains(term) {
-          return true;
-        }
-      }
-    }
-    false
+    !self.find_matches(event).is_empty()
   }
 }
 
 impl Default for GrepFilter {
   fn default() -> GrepFilter {
-    GrepFilter { terms: Vec::new() }
+    GrepFilter {
+      terms: Vec::new(),
+      properties: GREP_DEFAULT_PROPERTIES.iter().map(|prop| prop.to_string()).collect(),
+    }
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use super::*;
   use super::test::test_filter_event;
   use crate::testdata;
 
@@ -95,4 +128,30 @@ mod tests {
     );
     assert_eq!(false, filtered);
   }
+
+  #[test]
+  fn test_grep_regex() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["grep", "re:^festival"]);
+    assert_eq!(true, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["grep", "re:^jazz"]);
+    assert_eq!(false, filtered);
+  }
+
+  #[test]
+  fn test_grep_invalid_regex() {
+    assert!(SelectFilters::parse_from_args(&["grep", "re:("]).is_err());
+  }
+
+  #[test]
+  fn test_find_matches_properties() {
+    testdata::setup();
+    let event = KhEvent::from_str(testdata::TEST_EVENT_MULTIDAY, None).unwrap();
+
+    let filter = GrepFilter::new(&["lobby".to_string()], &[]).unwrap();
+    let expected = GrepMatch { property: "LOCATION".to_string(), value: "LDB Lobby".to_string(), start: 4, end: 9 };
+    assert_eq!(vec!(expected), filter.find_matches(&event));
+
+    let filter = GrepFilter::new(&["lobby".to_string()], &["summary".to_string()]).unwrap();
+    assert!(filter.find_matches(&event).is_empty());
+  }
 }
@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// How a `grep` or `prop` term is compared to property values. Matching is
+/// always case insensitive.
+///
+/// - `re:<pattern>` matches a regular expression
+/// - `~<word>` only matches whole words
+/// - anything else matches a substring
+///
+/// Prefixing any of these with `fold:` also ignores accents and other
+/// diacritics, so that `fold:morse` finds "Mörse".
+pub struct TermMatcher {
+  kind: MatchKind,
+  fold: bool,
+}
+
+enum MatchKind {
+  Substring(String),
+  Word(String),
+  Regex(Regex),
+}
+
+impl TermMatcher {
+  pub fn is_match(&self, value: &str) -> bool {
+    self.find(value).is_some()
+  }
+
+  /// Returns the byte offsets of the first match in `value`.
+  pub fn find(&self, value: &str) -> Option<(usize, usize)> {
+    let (haystack, offsets) = normalize(value, self.fold);
+    let (start, end) = match &self.kind {
+      MatchKind::Substring(term) => haystack.find(term.as_str()).map(|start| (start, start + term.len())),
+      MatchKind::Word(term) => find_word(&haystack, term),
+      MatchKind::Regex(regex) => regex.find(&haystack).map(|found| (found.start(), found.end())),
+    }?;
+    Some((offsets[start], offsets[end]))
+  }
+}
+
+impl FromStr for TermMatcher {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (term, fold) = if s.starts_with("fold:") { (&s[5..], true) } else { (s, false) };
+
+    let kind = if term.starts_with("re:") {
+      let pattern = if fold { strip_marks(&term[3..]) } else { term[3..].to_string() };
+      let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|err| format!("Invalid regular expression '{}': {}", &term[3..], err))?;
+      MatchKind::Regex(regex)
+    } else if term.starts_with('~') && term.len() > 1 {
+      MatchKind::Word(normalize(&term[1..], fold).0)
+    } else {
+      MatchKind::Substring(normalize(term, fold).0)
+    };
+
+    Ok(TermMatcher { kind, fold })
+  }
+}
+
+fn find_word(haystack: &str, word: &str) -> Option<(usize, usize)> {
+  haystack
+    .match_indices(word)
+    .map(|(start, _)| (start, start + word.len()))
+    .find(|(start, end)| {
+      let before = haystack[..*start].chars().next_back();
+      let after = haystack[*end..].chars().next();
+      !before.map_or(false, char::is_alphanumeric) && !after.map_or(false, char::is_alphanumeric)
+    })
+}
+
+fn strip_marks(text: &str) -> String {
+  text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+/// Lowercases `value` and, if `fold` is set, strips diacritics. Also returns
+/// the offset into `value` for every byte offset into the result, so that
+/// matches can be mapped back.
+fn normalize(value: &str, fold: bool) -> (String, Vec<usize>) {
+  let mut normalized = String::with_capacity(value.len());
+  let mut offsets = Vec::with_capacity(value.len() + 1);
+  for (index, c) in value.char_indices() {
+    let lowered: String = c.to_lowercase().collect();
+    let lowered = if fold { strip_marks(&lowered) } else { lowered };
+    offsets.extend(std::iter::repeat(index).take(lowered.len()));
+    normalized.push_str(&lowered);
+  }
+  offsets.push(value.len());
+  (normalized, offsets)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn matcher(term: &str) -> TermMatcher {
+    term.parse().unwrap()
+  }
+
+  #[test]
+  fn test_substring() {
+    assert_eq!(Some((4, 8)), matcher("jazz").find("Big JAZZ night"));
+    assert_eq!(Some((3, 10)), matcher("öltank").find("Im Öltank"));
+    assert_eq!(None, matcher("blues").find("Big Jazz night"));
+  }
+
+  #[test]
+  fn test_word() {
+    assert_eq!(Some((4, 8)), matcher("~jazz").find("Big Jazz night"));
+    assert_eq!(None, matcher("~jazz").find("Big Jazzband night"));
+    assert!(matcher("~night").is_match("Big Jazz night"));
+  }
+
+  #[test]
+  fn test_regex() {
+    assert!(matcher("re:^standup").is_match("Standup meeting"));
+    assert!(!matcher("re:^standup").is_match("Daily standup"));
+    assert_eq!(Some((6, 13)), matcher(r"re:s\w+up").find("Daily standup"));
+  }
+
+  #[test]
+  fn test_regex_invalid() {
+    assert!("re:(".parse::<TermMatcher>().is_err());
+  }
+
+  #[test]
+  fn test_fold() {
+    assert!(!matcher("morse").is_match("Mörse"));
+    assert!(matcher("fold:morse").is_match("Mörse"));
+    assert!(matcher("fold:mörse").is_match("Morse"));
+    assert!(matcher("fold:~ete").is_match("Fête d'été"));
+    assert!(matcher("fold:re:^fete").is_match("Fête d'été"));
+    assert_eq!(Some((3, 10)), matcher("fold:oltank").find("Im Öltank"));
+  }
+}
@@ -1,31 +1,39 @@
-use std::collections::HashMap;
-
 use crate::khevent::KhEvent;
 use crate::khline::{khlines_to_events, KhLine};
 
 use self::cal::CalendarFilter;
 use self::daterange::{SelectFilterFrom, SelectFilterTo};
+use self::expr::{ExprToken, FilterExpr};
 use self::grep::GrepFilter;
 use self::prop::PropFilter;
 use self::range::RangeFilter;
 
 mod cal;
 pub mod daterange;
-mod grep;
+mod expr;
+pub mod grep;
+pub mod matcher;
 mod prop;
 mod range;
 #[cfg(test)]
 mod test;
 
+/// Selection parsed from command line terms.
+///
+/// `from`, `to`, `in`, `on` and index ranges always restrict the selection,
+/// wherever they appear. The `grep`, `cal` and `prop` filters can be combined
+/// with `and`, `or`, `not` and parentheses, see `FilterExpr` for precedence.
+/// Their terms support regex, whole word and accent insensitive matching, see
+/// `TermMatcher`.
 pub struct SelectFilters {
   pub from: SelectFilterFrom,
   pub to: SelectFilterTo,
   pub range: Option<RangeFilter>,
-  others: Vec<Box<dyn SelectFilter>>,
+  others: Option<FilterExpr>,
 }
 
 pub trait SelectFilter {
-  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>);
+  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) -> Result<(), String>;
   fn is_not_empty(&self) -> bool;
   fn includes(&self, event: &KhEvent) -> bool;
 }
@@ -43,30 +51,33 @@ impl SelectFilters {
     let mut from: SelectFilterFrom = Default::default();
     let mut to: SelectFilterTo = Default::default();
     let mut range: Option<RangeFilter> = None;
-    let mut others: HashMap<&str, Box<dyn SelectFilter>> = HashMap::with_capacity(3);
-    others.insert("grep", Box::new(GrepFilter::default()));
-    others.insert("cal", Box::new(CalendarFilter::default()));
-    others.insert("prop", Box::new(PropFilter::default()));
+    let mut tokens: Vec<ExprToken> = Vec::new();
 
     let mut it = args.iter();
     while let Some(arg) = it.next() {
       match *arg {
         "from" => {
-          let term = it.next().unwrap();
+          let term = Self::next_date_term(&mut it)?;
           from = from.combine_with(&term.parse()?);
         }
         "to" => {
-          let term = it.next().unwrap();
+          let term = Self::next_date_term(&mut it)?;
           to = to.combine_with(&term.parse()?);
         }
         "in" | "on" => {
-          let term = it.next().unwrap();
+          let term = Self::next_date_term(&mut it)?;
           from = from.combine_with(&term.parse()?);
           to = to.combine_with(&term.parse()?);
         }
+        "and" => tokens.push(ExprToken::And),
+        "or" => tokens.push(ExprToken::Or),
+        "not" => tokens.push(ExprToken::Not),
+        "(" => tokens.push(ExprToken::Open),
+        ")" => tokens.push(ExprToken::Close),
         term => {
-          if let Some(filter) = others.get_mut(term) {
-            filter.add_term(&mut it);
+          if let Some(mut filter) = Self::new_filter(term) {
+            filter.add_term(&mut it)?;
+            tokens.push(ExprToken::Filter(filter));
           } else if let Ok(parsed_range) = term.parse::<RangeFilter>() {
             if !with_range {
               return Err("Range selector not allowed here!".to_string());
@@ -76,17 +87,13 @@ impl SelectFilters {
             }
             range = Some(parsed_range);
           } else {
-            return Err("select [from|to|in|on|grep|cal parameter]+".to_string());
+            return Err("select [from|to|in|on|grep|cal|prop parameter|and|or|not|(|)]+".to_string());
           }
         }
       }
     }
 
-    let others = others
-      .drain()
-      .map(|x| x.1)
-      .filter(|filter| filter.is_not_empty())
-      .collect();
+    let others = FilterExpr::parse(tokens)?;
 
     Ok(SelectFilters {
       from,
@@ -96,6 +103,28 @@ impl SelectFilters {
     })
   }
 
+  /// Takes the date term after `from`, `to`, `in` or `on`, joining
+  /// `next`, `last` and `this` with the word that follows them.
+  fn next_date_term(it: &mut dyn Iterator<Item = &&str>) -> Result<String, String> {
+    let term = it.next().ok_or("Missing date after date selector")?;
+    match *term {
+      "next" | "last" | "this" => {
+        let unit = it.next().ok_or_else(|| format!("Missing unit after '{}'", term))?;
+        Ok(format!("{} {}", term, unit))
+      }
+      _ => Ok(term.to_string()),
+    }
+  }
+
+  fn new_filter(name: &str) -> Option<Box<dyn SelectFilter>> {
+    match name {
+      "grep" => Some(Box::new(GrepFilter::default())),
+      "cal" => Some(Box::new(CalendarFilter::default())),
+      "prop" => Some(Box::new(PropFilter::default())),
+      _ => None,
+    }
+  }
+
   fn line_is_from(&self, event: &KhEvent) -> bool {
     let starts_after = self.from.includes_date(event.get_start().unwrap().into());
     let ends_after = self.from.includes_date(event.get_end().unwrap().into());
@@ -115,7 +144,7 @@ impl SelectFilters {
   }
 
   fn others(&self, event: &KhEvent) -> bool {
-    self.others.is_empty() || self.others.iter().any(|filter| filter.includes(event))
+    self.others.as_ref().map_or(true, |expr| expr.includes(event))
   }
 
   pub fn is_selected(&self, event: &KhEvent) -> bool {
@@ -2,21 +2,19 @@ use std::collections::HashMap;
 
 use super::*;
 
+use super::matcher::TermMatcher;
 use crate::khevent::KhEvent;
 
 pub struct PropFilter {
-  terms: HashMap<String, Vec<String>>,
+  terms: HashMap<String, Vec<TermMatcher>>,
 }
 
 impl SelectFilter for PropFilter {
-  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) {
-    let term = it.next().unwrap().to_uppercase();
-    let value = it.next().unwrap();
-    self
-      .terms
-      .entry(term)
-      .and_modify(|x| x.push(value.to_lowercase()))
-      .or_insert_with(|| vec![value.to_lowercase()]);
+  fn add_term(&mut self, it: &mut dyn Iterator<Item = &&str>) -> Result<(), String> {
+    let term = it.next().ok_or("prop needs a property name")?.to_uppercase();
+    let value = it.next().ok_or("prop needs a value")?.parse::<TermMatcher>()?;
+    self.terms.entry(term).or_insert_with(Vec::new).push(value);
+    Ok(())
   }
 
   fn is_not_empty(&self) -> bool {
@@ -26,8 +24,8 @@ impl SelectFilter for PropFilter {
   fn includes(&self, event: &KhEvent) -> bool {
     for (term, values) in &self.terms {
       for prop in event.get_properties_by_name(term) {
-        let value = prop.get_value().to_lowercase();
-        if values.iter().any(|x| value.contains(x)) {
+        let value = prop.get_value();
+        if values.iter().any(|x| x.is_match(&value)) {
           return true;
         }
       }
@@ -78,4 +76,20 @@ mod tests {
     );
     assert_eq!(false, filtered);
   }
+
+  #[test]
+  fn test_prop_word() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["prop", "SUMMARY", "~jazz"]);
+    assert_eq!(true, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["prop", "SUMMARY", "~jaz"]);
+    assert_eq!(false, filtered);
+  }
+
+  #[test]
+  fn test_prop_fold() {
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["prop", "SUMMARY", "montréal"]);
+    assert_eq!(false, filtered);
+    let filtered = test_filter_event(&testdata::TEST_EVENT_MULTIDAY, None, &["prop", "SUMMARY", "fold:montréal"]);
+    assert_eq!(true, filtered);
+  }
 }
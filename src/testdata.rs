@@ -166,6 +166,123 @@ pub static TEST_EVENT_RECUR: &str = indoc!("
 ");
 
 
+pub static TEST_EVENT_RECUR_OVERRIDE: &str = indoc!("
+    BEGIN:VCALENDAR
+    VERSION:2.0
+    PRODID:-//ABC Corporation//NONSGML My Product//EN
+    BEGIN:VEVENT
+    UID:autocryptthursday
+    DTSTART;VALUE=DATE:20181011
+    DURATION:P1D
+    SUMMARY:Autocrypt Thursdays
+    RRULE:FREQ=WEEKLY;COUNT=3
+    END:VEVENT
+    BEGIN:VEVENT
+    UID:autocryptthursday
+    RECURRENCE-ID;VALUE=DATE:20181018
+    DTSTART;VALUE=DATE:20181019
+    DURATION:P1D
+    SUMMARY:Autocrypt Friday
+    END:VEVENT
+    END:VCALENDAR
+");
+
+pub static TEST_EVENT_DST: &str = indoc!("
+    BEGIN:VCALENDAR
+    VERSION:2.0
+    PRODID:-//ABC Corporation//NONSGML My Product//EN
+    BEGIN:VTIMEZONE
+    TZID:Europe/Berlin
+    BEGIN:DAYLIGHT
+    DTSTART:19700329T020000
+    TZNAME:CEST
+    TZOFFSETFROM:+0100
+    TZOFFSETTO:+0200
+    RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3
+    END:DAYLIGHT
+    BEGIN:STANDARD
+    DTSTART:19701025T030000
+    TZNAME:CET
+    TZOFFSETFROM:+0200
+    TZOFFSETTO:+0100
+    RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10
+    END:STANDARD
+    END:VTIMEZONE
+    BEGIN:VEVENT
+    UID:nightowl
+    DTSTAMP:20190301T120000Z
+    DTSTART;TZID=Europe/Berlin:20190331T013000
+    DTEND;TZID=Europe/Berlin:20190331T040000
+    SUMMARY:Night owl
+    END:VEVENT
+    END:VCALENDAR
+");
+
+pub static TEST_IMPORT_MULTIPLE: &str = indoc!("
+    BEGIN:VCALENDAR
+    VERSION:2.0
+    PRODID:-//ABC Corporation//NONSGML My Product//EN
+    BEGIN:VTIMEZONE
+    TZID:Europe/Berlin
+    BEGIN:DAYLIGHT
+    DTSTART:19700329T020000
+    TZNAME:CEST
+    TZOFFSETFROM:+0100
+    TZOFFSETTO:+0200
+    RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3
+    END:DAYLIGHT
+    BEGIN:STANDARD
+    DTSTART:19701025T030000
+    TZNAME:CET
+    TZOFFSETFROM:+0200
+    TZOFFSETTO:+0100
+    RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10
+    END:STANDARD
+    END:VTIMEZONE
+    BEGIN:VTIMEZONE
+    TZID:America/New_York
+    BEGIN:DAYLIGHT
+    DTSTART:19700308T020000
+    TZNAME:EDT
+    TZOFFSETFROM:-0500
+    TZOFFSETTO:-0400
+    RRULE:FREQ=YEARLY;BYDAY=2SU;BYMONTH=3
+    END:DAYLIGHT
+    BEGIN:STANDARD
+    DTSTART:19701101T020000
+    TZNAME:EST
+    TZOFFSETFROM:-0400
+    TZOFFSETTO:-0500
+    RRULE:FREQ=YEARLY;BYDAY=1SU;BYMONTH=11
+    END:STANDARD
+    END:VTIMEZONE
+    BEGIN:VEVENT
+    UID:import-berlin
+    DTSTAMP:20181201T120000Z
+    DTSTART;TZID=Europe/Berlin:20190107T100000
+    DTEND;TZID=Europe/Berlin:20190107T110000
+    SUMMARY:Weekly in Berlin
+    RRULE:FREQ=WEEKLY;COUNT=3
+    END:VEVENT
+    BEGIN:VEVENT
+    UID:import-newyork
+    DTSTAMP:20181201T120000Z
+    DTSTART;TZID=America/New_York:20190108T090000
+    DTEND;TZID=America/New_York:20190108T100000
+    SUMMARY:Meeting in New York
+    SEQUENCE:2
+    END:VEVENT
+    BEGIN:VEVENT
+    UID:import-berlin
+    DTSTAMP:20181201T120000Z
+    RECURRENCE-ID;TZID=Europe/Berlin:20190114T100000
+    DTSTART;TZID=Europe/Berlin:20190114T120000
+    DTEND;TZID=Europe/Berlin:20190114T130000
+    SUMMARY:Weekly in Berlin, later
+    END:VEVENT
+    END:VCALENDAR
+");
+
 pub static TEST_EVENT_WITH_TIMEZONE_COMPONENT: &str = indoc!("
     BEGIN:VCALENDAR
     VERSION:2.0
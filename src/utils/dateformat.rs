@@ -0,0 +1,229 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+use std::fmt::Write;
+use std::str::FromStr;
+use yansi::Style;
+
+use crate::utils::template;
+
+/// The languages weekday and month names can be printed in. chrono only
+/// knows English names, the others are substituted before formatting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+  En,
+  De,
+}
+
+impl FromStr for Locale {
+  type Err = String;
+
+  fn from_str(locale: &str) -> Result<Self, Self::Err> {
+    let language = locale.split(|c| c == '_' || c == '-' || c == '.').next().unwrap_or("");
+    match language.to_lowercase().as_str() {
+      "en" | "c" | "posix" => Ok(Locale::En),
+      "de" => Ok(Locale::De),
+      _ => Err(format!("unsupported locale {}", locale)),
+    }
+  }
+}
+
+impl Locale {
+  fn weekday_name(self, weekday: Weekday) -> Option<&'static str> {
+    let names = match self {
+      Locale::En => return None,
+      Locale::De => ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+    };
+    Some(names[weekday.num_days_from_monday() as usize])
+  }
+
+  fn weekday_abbreviation(self, weekday: Weekday) -> Option<&'static str> {
+    let names = match self {
+      Locale::En => return None,
+      Locale::De => ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    };
+    Some(names[weekday.num_days_from_monday() as usize])
+  }
+
+  fn month_name(self, month0: u32) -> Option<&'static str> {
+    let names = match self {
+      Locale::En => return None,
+      Locale::De => [
+        "Januar", "Februar", "März", "April", "Mai", "Juni",
+        "Juli", "August", "September", "Oktober", "November", "Dezember",
+      ],
+    };
+    Some(names[month0 as usize])
+  }
+
+  fn month_abbreviation(self, month0: u32) -> Option<&'static str> {
+    let names = match self {
+      Locale::En => return None,
+      Locale::De => ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+    };
+    Some(names[month0 as usize])
+  }
+
+  /// Replaces `%A`, `%a`, `%B` and `%b` in a strftime pattern with the names
+  /// in this locale, leaving everything else to chrono.
+  fn localize_pattern(self, pattern: &str, weekday: Weekday, month0: u32) -> String {
+    let mut result = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+      if c != '%' {
+        result.push(c);
+        continue;
+      }
+      let specifier = match chars.next() {
+        Some(specifier) => specifier,
+        None => {
+          result.push('%');
+          break;
+        }
+      };
+      let name = match specifier {
+        'A' => self.weekday_name(weekday),
+        'a' => self.weekday_abbreviation(weekday),
+        'B' => self.month_name(month0),
+        'b' | 'h' => self.month_abbreviation(month0),
+        _ => None,
+      };
+      match name {
+        Some(name) => result.push_str(name),
+        None => {
+          result.push('%');
+          result.push(specifier);
+        }
+      }
+    }
+    result
+  }
+}
+
+/// How dates and times are printed, see the `[format]` config section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateFormat {
+  pub locale: Locale,
+  pub date_format: String,
+  pub time_format: String,
+  pub header_format: String,
+}
+
+impl Default for DateFormat {
+  fn default() -> Self {
+    DateFormat {
+      locale: Locale::En,
+      date_format: "%Y-%m-%d".to_string(),
+      time_format: "%H:%M".to_string(),
+      header_format: "{date}, {weekday}".to_string(),
+    }
+  }
+}
+
+impl DateFormat {
+  /// Formats the date with a strftime pattern, with names in this locale.
+  pub fn format(&self, date: NaiveDate, pattern: &str) -> String {
+    let pattern = self.locale.localize_pattern(pattern, date.weekday(), date.month0());
+    date.format(&pattern).to_string()
+  }
+
+  pub fn format_date(&self, date: NaiveDate) -> String {
+    self.format(date, &self.date_format)
+  }
+
+  pub fn format_time(&self, datetime: &NaiveDateTime) -> String {
+    let pattern = self.locale.localize_pattern(&self.time_format, datetime.weekday(), datetime.month0());
+    datetime.format(&pattern).to_string()
+  }
+
+  /// The width of a formatted time, for aligning columns.
+  pub fn time_width(&self) -> usize {
+    let noon = NaiveDate::from_ymd(2000, 1, 1).and_hms(12, 0, 0);
+    self.format_time(&noon).chars().count()
+  }
+
+  /// Renders `header_format`, a template with the fields `date` (printed in
+  /// bold), `weekday` and `month`.
+  pub fn format_header(&self, date: NaiveDate) -> Result<String, String> {
+    template::render(&self.header_format, |field| match field {
+      "date" => Some((self.format_date(date), Style::default().bold())),
+      "weekday" => Some((self.format(date, "%A"), Style::default())),
+      "month" => Some((self.format(date, "%B"), Style::default())),
+      _ => None,
+    })
+  }
+}
+
+/// Checks a strftime pattern before it is used, chrono panics when it formats
+/// an invalid one.
+pub fn check_pattern(pattern: &str) -> Result<(), String> {
+  if StrftimeItems::new(pattern).any(|item| item == Item::Error) {
+    Err(format!("invalid strftime pattern {}", pattern))
+  } else {
+    Ok(())
+  }
+}
+
+/// Like `check_pattern`, and also rejects patterns that print a time of day,
+/// which a date does not have.
+pub fn check_date_pattern(pattern: &str) -> Result<(), String> {
+  check_pattern(pattern)?;
+  let mut formatted = String::new();
+  write!(formatted, "{}", NaiveDate::from_ymd(2000, 1, 1).format(pattern))
+    .map_err(|_| format!("date pattern {} prints a time", pattern))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_locale_from_str() {
+    assert_eq!(Locale::De, "de_DE.UTF-8".parse::<Locale>().unwrap());
+    assert_eq!(Locale::En, "en".parse::<Locale>().unwrap());
+    assert!("xx".parse::<Locale>().is_err());
+  }
+
+  #[test]
+  fn test_format_header() {
+    yansi::Paint::disable();
+    let date = NaiveDate::from_ymd(2020, 4, 10);
+
+    assert_eq!("2020-04-10, Friday", DateFormat::default().format_header(date).unwrap());
+
+    let german = DateFormat {
+      locale: Locale::De,
+      date_format: "%d.%m.%Y".to_string(),
+      header_format: "{weekday}, {date} ({month})".to_string(),
+      ..DateFormat::default()
+    };
+    assert_eq!("Freitag, 10.04.2020 (April)", german.format_header(date).unwrap());
+  }
+
+  #[test]
+  fn test_format_localized() {
+    let german = DateFormat { locale: Locale::De, ..DateFormat::default() };
+    let date = NaiveDate::from_ymd(2019, 3, 17);
+
+    assert_eq!("So 17. Mär / März 2019, 100%", german.format(date, "%a %d. %b / %B %Y, 100%%"));
+  }
+
+  #[test]
+  fn test_format_time() {
+    let datetime = NaiveDate::from_ymd(2019, 3, 17).and_hms(15, 30, 0);
+    let twelve_hour = DateFormat { time_format: "%I:%M%p".to_string(), ..DateFormat::default() };
+
+    assert_eq!("15:30", DateFormat::default().format_time(&datetime));
+    assert_eq!("03:30PM", twelve_hour.format_time(&datetime));
+    assert_eq!(7, twelve_hour.time_width());
+  }
+
+  #[test]
+  fn test_check_pattern() {
+    assert!(check_pattern("%Y-%m-%d %H:%M, 100%%").is_ok());
+    assert!(check_pattern("%Y-%m-%").is_err());
+    assert!(check_pattern("%Q").is_err());
+
+    assert!(check_date_pattern("%d.%m.%Y").is_ok());
+    assert!(check_date_pattern("%Y-%m-%d %H:%M").is_err());
+  }
+}
@@ -1,17 +1,211 @@
 use chrono::*;
 
+use std::cmp;
 use std::path::PathBuf;
 use std::env;
 use crate::utils::fileutil;
 
+/// Parses a single day. Besides `%Y-%m-%d`, this understands `today`, `now`,
+/// `tomorrow`, `yesterday`, weekday names (the next such day, including today)
+/// and offsets from today like `+3d`, `-2w`, `+1mo` or `-1y`. Months are `mo`,
+/// since `m` means minutes in durations like the offset of `modify shift`.
 pub fn date_from_str(date_str: &str) -> ParseResult<Date<Local>> {
-  if date_str  == "today" || date_str == "now" {
-    return Ok(Local::now().date());
+  let today = Local::now().date();
+  match date_str {
+    "today" | "now" => return Ok(today),
+    "tomorrow" => return Ok(today.succ()),
+    "yesterday" => return Ok(today.pred()),
+    _ => (),
+  }
+  if let Some(weekday) = weekday_from_str(date_str) {
+    let days_ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    return Ok(today + Duration::days(i64::from(days_ahead)));
+  }
+  if let Some(date) = date_from_offset(today, date_str) {
+    return Ok(date);
   }
   let naive_date = &NaiveDate::parse_from_str(date_str, "%Y-%m-%d")?;
   Ok(Local.from_local_date(naive_date).unwrap())
 }
 
+/// Parses a day, week, month or year and returns its first and last day.
+///
+/// Months are given as `%Y-%m` or by name (in the current year), years as
+/// `%Y`. `next`, `last` and `this` can be combined with `week`, `month`,
+/// `year` or a weekday name, e.g. `next friday` or `last month`.
+pub fn daterange_from_str(range_str: &str) -> Result<(Date<Local>, Date<Local>), String> {
+  if let Ok(date) = date_from_str(range_str) {
+    return Ok((date, date));
+  }
+  if let (Ok(begin), Ok(end)) = (week_from_str_begin(range_str), week_from_str_end(range_str)) {
+    return Ok((begin, end));
+  }
+  if let Some(range) = month_from_str(range_str) {
+    return Ok(range);
+  }
+  if let Some(range) = year_from_str(range_str) {
+    return Ok(range);
+  }
+  if let Some(range) = relative_range_from_str(range_str) {
+    return Ok(range);
+  }
+  Err(format!("Could not parse date '{}'", range_str))
+}
+
+fn weekday_from_str(weekday_str: &str) -> Option<Weekday> {
+  match weekday_str.to_lowercase().as_str() {
+    "monday" | "mon" => Some(Weekday::Mon),
+    "tuesday" | "tue" => Some(Weekday::Tue),
+    "wednesday" | "wed" => Some(Weekday::Wed),
+    "thursday" | "thu" => Some(Weekday::Thu),
+    "friday" | "fri" => Some(Weekday::Fri),
+    "saturday" | "sat" => Some(Weekday::Sat),
+    "sunday" | "sun" => Some(Weekday::Sun),
+    _ => None,
+  }
+}
+
+fn month_number_from_str(month_str: &str) -> Option<u32> {
+  let months = [
+    "january", "february", "march", "april", "may", "june",
+    "july", "august", "september", "october", "november", "december",
+  ];
+  let month_str = month_str.to_lowercase();
+  if month_str.len() < 3 {
+    return None;
+  }
+  months
+    .iter()
+    .position(|month| month.starts_with(&month_str))
+    .map(|index| index as u32 + 1)
+}
+
+fn date_from_offset(date: Date<Local>, offset_str: &str) -> Option<Date<Local>> {
+  let sign = match offset_str.chars().next()? {
+    '+' => 1,
+    '-' => -1,
+    _ => return None,
+  };
+  let unsigned_str = &offset_str[1..];
+  let unit_start = unsigned_str.find(|c: char| !c.is_ascii_digit())?;
+  let (number, unit) = unsigned_str.split_at(unit_start);
+  if number.is_empty() {
+    return None;
+  }
+  let amount = number.parse::<i64>().ok()? * sign;
+  match unit {
+    "d" => date.checked_add_signed(Duration::days(amount)),
+    "w" => date.checked_add_signed(Duration::weeks(amount)),
+    "mo" => add_months(date, amount),
+    "y" => add_months(date, amount * 12),
+    _ => None,
+  }
+}
+
+fn add_months(date: Date<Local>, months: i64) -> Option<Date<Local>> {
+  let total_months = i64::from(date.year()) * 12 + i64::from(date.month0()) + months;
+  let year = (total_months / 12) as i32;
+  let month = (total_months % 12) as u32 + 1;
+  let day = cmp::min(date.day(), last_day_of_month(year, month));
+  Local.ymd_opt(year, month, day).single()
+}
+
+fn last_day_of_month(year: i32, month: u32) -> u32 {
+  let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+  NaiveDate::from_ymd(next_year, next_month, 1).pred().day()
+}
+
+pub fn month_range(year: i32, month: u32) -> (Date<Local>, Date<Local>) {
+  (Local.ymd(year, month, 1), Local.ymd(year, month, last_day_of_month(year, month)))
+}
+
+fn year_range(year: i32) -> (Date<Local>, Date<Local>) {
+  (Local.ymd(year, 1, 1), Local.ymd(year, 12, 31))
+}
+
+fn week_range(date: Date<Local>) -> (Date<Local>, Date<Local>) {
+  let week = date.iso_week();
+  (Local.isoywd(week.year(), week.week(), Weekday::Mon), Local.isoywd(week.year(), week.week(), Weekday::Sun))
+}
+
+fn month_from_str(month_str: &str) -> Option<(Date<Local>, Date<Local>)> {
+  if let Ok(date) = NaiveDate::parse_from_str(&format!("{}-01", month_str), "%Y-%m-%d") {
+    return Some(month_range(date.year(), date.month()));
+  }
+  let month = month_number_from_str(month_str)?;
+  Some(month_range(Local::now().year(), month))
+}
+
+fn year_from_str(year_str: &str) -> Option<(Date<Local>, Date<Local>)> {
+  if year_str.len() != 4 {
+    return None;
+  }
+  let year = year_str.parse::<i32>().ok()?;
+  Some(year_range(year))
+}
+
+fn relative_range_from_str(range_str: &str) -> Option<(Date<Local>, Date<Local>)> {
+  let parts: Vec<&str> = range_str.split_whitespace().collect();
+  if parts.len() != 2 {
+    return None;
+  }
+  let offset = match parts[0] {
+    "next" => 1,
+    "last" => -1,
+    "this" => 0,
+    _ => return None,
+  };
+  let today = Local::now().date();
+  match parts[1] {
+    "week" => Some(week_range(today + Duration::weeks(offset))),
+    "month" => {
+      let date = add_months(today.with_day(1)?, offset)?;
+      Some(month_range(date.year(), date.month()))
+    }
+    "year" => Some(year_range(today.year() + offset as i32)),
+    weekday_str => {
+      let weekday = weekday_from_str(weekday_str)?;
+      let week = (today + Duration::weeks(offset)).iso_week();
+      let date = Local.isoywd(week.year(), week.week(), weekday);
+      Some((date, date))
+    }
+  }
+}
+
+/// Parses durations like `1h30m`, `45m`, `2d` or `1w` into seconds. A
+/// leading `-` makes the duration negative.
+pub fn seconds_from_duration_str(duration_str: &str) -> Result<i64, String> {
+  let invalid = || format!("invalid duration {}", duration_str);
+  let (sign, unsigned_str) = match duration_str.chars().next() {
+    Some('-') => (-1, &duration_str[1..]),
+    Some('+') => (1, &duration_str[1..]),
+    _ => (1, duration_str),
+  };
+  let mut seconds = 0;
+  let mut number = String::new();
+  for c in unsigned_str.chars() {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+    let unit = match c {
+      'w' => 7 * 24 * 60 * 60,
+      'd' => 24 * 60 * 60,
+      'h' => 60 * 60,
+      'm' => 60,
+      's' => 1,
+      _ => return Err(invalid()),
+    };
+    let amount = number.parse::<i64>().map_err(|_| invalid())?;
+    seconds += amount * unit;
+    number.clear();
+  }
+  if !number.is_empty() || seconds == 0 {
+    return Err(invalid());
+  }
+  Ok(sign * seconds)
+}
+
 pub fn datetime_from_str(datetime_str: &str) -> ParseResult<DateTime<Local>> {
   if datetime_str == "now" {
     return Ok(Local::now());
@@ -95,6 +289,86 @@ mod tests {
     assert_eq!(Local::now().date(), date);
   }
 
+  #[test]
+  fn test_date_from_str_relative() {
+    let today = Local::now().date();
+    assert_eq!(today.succ(), date_from_str("tomorrow").unwrap());
+    assert_eq!(today.pred(), date_from_str("yesterday").unwrap());
+    assert_eq!(today + Duration::days(3), date_from_str("+3d").unwrap());
+    assert_eq!(today - Duration::weeks(2), date_from_str("-2w").unwrap());
+    assert_eq!(add_months(today, 1).unwrap(), date_from_str("+1mo").unwrap());
+    assert!(date_from_str("+d").is_err());
+    assert!(date_from_str("+3x").is_err());
+    assert!(date_from_str("+1m").is_err());
+    assert!(date_from_str("+-3d").is_err());
+    assert!(date_from_str("--3d").is_err());
+  }
+
+  #[test]
+  fn test_date_from_str_weekday() {
+    let today = Local::now().date();
+    let date = date_from_str("friday").unwrap();
+    assert_eq!(Weekday::Fri, date.weekday());
+    assert!(today <= date && date < today + Duration::weeks(1));
+    assert_eq!(date, date_from_str("Fri").unwrap());
+  }
+
+  #[test]
+  fn test_add_months() {
+    let date = Local.ymd(2019, 1, 31);
+    assert_eq!(Local.ymd(2019, 2, 28), add_months(date, 1).unwrap());
+    assert_eq!(Local.ymd(2018, 12, 31), add_months(date, -1).unwrap());
+    assert_eq!(Local.ymd(2020, 1, 31), add_months(date, 12).unwrap());
+  }
+
+  #[test]
+  fn test_daterange_from_str() {
+    assert_eq!((Local.ymd(2019, 3, 1), Local.ymd(2019, 3, 31)), daterange_from_str("2019-03").unwrap());
+    assert_eq!((Local.ymd(2019, 1, 1), Local.ymd(2019, 12, 31)), daterange_from_str("2019").unwrap());
+    assert_eq!((Local.ymd(2018, 12, 10), Local.ymd(2018, 12, 16)), daterange_from_str("2018-W50").unwrap());
+    assert_eq!((Local.ymd(2018, 12, 10), Local.ymd(2018, 12, 10)), daterange_from_str("2018-12-10").unwrap());
+    let year = Local::now().year();
+    assert_eq!((Local.ymd(year, 2, 1), Local.ymd(year, 2, last_day_of_month(year, 2))), daterange_from_str("february").unwrap());
+    assert_eq!(daterange_from_str("feb"), daterange_from_str("february"));
+    assert!(daterange_from_str("ma").is_err());
+    assert!(daterange_from_str("notadate").is_err());
+  }
+
+  #[test]
+  fn test_daterange_from_str_next_last() {
+    let today = Local::now().date();
+    let (begin, end) = daterange_from_str("next week").unwrap();
+    assert_eq!(Weekday::Mon, begin.weekday());
+    assert_eq!(begin + Duration::days(6), end);
+    assert!(today < begin && begin <= today + Duration::weeks(1));
+
+    let (begin, end) = daterange_from_str("last friday").unwrap();
+    assert_eq!(begin, end);
+    assert_eq!(Weekday::Fri, begin.weekday());
+    assert_eq!(today.iso_week().week(), (begin + Duration::weeks(1)).iso_week().week());
+
+    let (begin, _) = daterange_from_str("next month").unwrap();
+    assert_eq!(1, begin.day());
+    assert_eq!(today.month() % 12 + 1, begin.month());
+
+    let (begin, end) = daterange_from_str("this year").unwrap();
+    assert_eq!((Local.ymd(today.year(), 1, 1), Local.ymd(today.year(), 12, 31)), (begin, end));
+    assert!(daterange_from_str("next fortnight").is_err());
+  }
+
+  #[test]
+  fn test_seconds_from_duration_str() {
+    assert_eq!(Ok(5400), seconds_from_duration_str("1h30m"));
+    assert_eq!(Ok(86400), seconds_from_duration_str("+1d"));
+    assert_eq!(Ok(-1800), seconds_from_duration_str("-30m"));
+    assert_eq!(Ok(1209600), seconds_from_duration_str("2w"));
+    assert!(seconds_from_duration_str("").is_err());
+    assert!(seconds_from_duration_str("-").is_err());
+    assert!(seconds_from_duration_str("90").is_err());
+    assert!(seconds_from_duration_str("1x").is_err());
+    assert!(seconds_from_duration_str("+-30m").is_err());
+  }
+
   #[test]
   #[should_panic]
   fn test_date_from_str_negative() {
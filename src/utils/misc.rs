@@ -19,6 +19,10 @@ pub fn format_duration(duration: &time::Duration) -> impl Display {
   duration.as_millis()
 }
 
+/// bucket that lists the master of every recurring event, so that recurrences
+/// can be unrolled at query time
+pub static RECUR_BUCKET: &str = "recurring";
+
 pub fn get_bucket_for_date(date: Date<Local>) -> String {
   date.format("%G-W%V").to_string()
 }
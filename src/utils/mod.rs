@@ -1,5 +1,7 @@
+pub mod dateformat;
 pub mod dateutil;
 pub mod lock;
 pub mod fileutil;
 pub mod misc;
 pub mod stdioutils;
+pub mod template;
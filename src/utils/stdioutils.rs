@@ -1,3 +1,4 @@
+use std::fs;
 use std::io;
 use std::io::BufRead;
 
@@ -7,6 +8,16 @@ pub fn read_single_char_from_stdin() -> io::Result<char> {
   read_single_char(stdinlock)
 }
 
+/// Reads a single char from stdin, or from the terminal if stdin is piped.
+pub fn read_single_char_from_terminal() -> io::Result<char> {
+  if is_stdin_tty() {
+    read_single_char_from_stdin()
+  } else {
+    let tty = fs::File::open("/dev/tty")?;
+    read_single_char(io::BufReader::new(tty))
+  }
+}
+
 pub fn read_single_char(mut source: impl BufRead) -> io::Result<char> {
   let mut buf = String::new();
   source.read_line(&mut buf)?;
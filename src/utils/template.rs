@@ -0,0 +1,161 @@
+use yansi::Style;
+
+/// Renders a template like `"{index:>3} {start}-{end} {summary:.20}"`.
+///
+/// Each `{name}` or `{name:spec}` is replaced by the value `lookup` returns
+/// for `name`. The spec is an optional alignment (`<`, `>` or `^`), an
+/// optional minimum width and an optional `.precision` that truncates the
+/// value, counted in characters. Padding is applied before the value is
+/// painted with its style. Use `{{` and `}}` for literal braces.
+pub fn render<F>(template: &str, lookup: F) -> Result<String, String>
+where
+  F: Fn(&str) -> Option<(String, Style)>,
+{
+  let mut result = String::new();
+  let mut chars = template.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '{' if chars.peek() == Some(&'{') => {
+        chars.next();
+        result.push('{');
+      }
+      '}' if chars.peek() == Some(&'}') => {
+        chars.next();
+        result.push('}');
+      }
+      '{' => {
+        let mut placeholder = String::new();
+        loop {
+          match chars.next() {
+            Some('}') => break,
+            Some(c) => placeholder.push(c),
+            None => return Err(format!("unmatched {{ in template \"{}\"", template)),
+          }
+        }
+        result.push_str(&render_placeholder(&placeholder, &lookup)?);
+      }
+      '}' => return Err(format!("unmatched }} in template \"{}\"", template)),
+      c => result.push(c),
+    }
+  }
+
+  Ok(result)
+}
+
+/// Checks that `template` can be rendered with the given field names, so that
+/// errors show up once instead of on every rendered line.
+pub fn check(template: &str, fields: &[&str]) -> Result<(), String> {
+  render(template, |field| {
+    if fields.contains(&field) {
+      Some((String::new(), Style::default()))
+    } else {
+      None
+    }
+  })
+  .map(|_| ())
+}
+
+fn render_placeholder<F>(placeholder: &str, lookup: &F) -> Result<String, String>
+where
+  F: Fn(&str) -> Option<(String, Style)>,
+{
+  let mut parts = placeholder.splitn(2, ':');
+  let name = parts.next().unwrap_or("").trim();
+  let spec = FieldSpec::parse(parts.next().unwrap_or(""))?;
+
+  let (value, style) = lookup(name).ok_or_else(|| format!("unknown template field {{{}}}", name))?;
+  let value = spec.apply(&value);
+  if style == Style::default() {
+    Ok(value)
+  } else {
+    Ok(style.paint(value).to_string())
+  }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct FieldSpec {
+  align: Option<char>,
+  width: usize,
+  precision: Option<usize>,
+}
+
+impl FieldSpec {
+  fn parse(spec: &str) -> Result<Self, String> {
+    let mut result = FieldSpec::default();
+
+    let mut rest = spec;
+    if let Some(align) = rest.chars().next().filter(|c| "<>^".contains(*c)) {
+      result.align = Some(align);
+      rest = &rest[1..];
+    }
+
+    let mut parts = rest.splitn(2, '.');
+    let width = parts.next().unwrap_or("");
+    if !width.is_empty() {
+      result.width = width.parse().map_err(|_| format!("invalid width in {{:{}}}", spec))?;
+    }
+    if let Some(precision) = parts.next() {
+      result.precision = Some(precision.parse().map_err(|_| format!("invalid precision in {{:{}}}", spec))?);
+    }
+
+    Ok(result)
+  }
+
+  fn apply(&self, value: &str) -> String {
+    let value: String = match self.precision {
+      Some(precision) => value.chars().take(precision).collect(),
+      None => value.to_string(),
+    };
+    let width = self.width;
+    match self.align {
+      Some('>') => format!("{:>width$}", value, width = width),
+      Some('^') => format!("{:^width$}", value, width = width),
+      _ => format!("{:<width$}", value, width = width),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lookup(name: &str) -> Option<(String, Style)> {
+    match name {
+      "index" => Some(("7".to_string(), Style::default())),
+      "summary" => Some(("Festival".to_string(), Style::default())),
+      "location" => Some(("".to_string(), Style::default())),
+      _ => None,
+    }
+  }
+
+  #[test]
+  fn test_render() {
+    let line = render("{index:>3} {summary} @ {location}", lookup).unwrap();
+    assert_eq!("  7 Festival @ ", line);
+  }
+
+  #[test]
+  fn test_render_spec() {
+    assert_eq!("[Fest]", render("[{summary:.4}]", lookup).unwrap());
+    assert_eq!("[Fest  ]", render("[{summary:6.4}]", lookup).unwrap());
+    assert_eq!("[ Festival ]", render("[{summary:^10}]", lookup).unwrap());
+    assert_eq!("{7}", render("{{{index}}}", lookup).unwrap());
+  }
+
+  #[test]
+  fn test_render_errors() {
+    assert!(render("{nope}", lookup).is_err());
+    assert!(render("{index:x}", lookup).is_err());
+    assert!(render("index}", lookup).is_err());
+    assert!(render("{index", lookup).is_err());
+    assert!(render("{summary:<10", lookup).is_err());
+  }
+
+  #[test]
+  fn test_check() {
+    assert!(check("{index:>3} {summary}", &["index", "summary"]).is_ok());
+    assert!(check("{index} {location}", &["index", "summary"]).is_err());
+    assert!(check("{index", &["index"]).is_err());
+  }
+}